@@ -0,0 +1,105 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+import importlib.util
+from abc import ABC, abstractmethod
+from dataclasses import dataclass
+from pathlib import Path
+from typing import Iterable, List, Tuple
+
+from gprofiler.analysis.findings import Finding
+from gprofiler.analysis.sinks import SINK_PLUGIN_ENTRY_POINT, SampleSink
+from gprofiler.gprofiler_types import ProcessToProfileData
+from gprofiler.log import get_logger_adapter
+
+logger = get_logger_adapter(__name__)
+
+# the function each plugin module must expose, returning the analyzers it defines.
+PLUGIN_ENTRY_POINT = "get_analyzers"
+
+
+@dataclass
+class AnalysisOptions:
+    # minimal share (0-1) of the samples of a process that must be under the analyzed code to flag it.
+    hotspot_threshold: float
+
+
+class Analyzer(ABC):
+    """
+    An analysis pass, run over the profiles collected in a session once it ends.
+    """
+
+    name: str
+
+    @abstractmethod
+    def analyze(self, process_profiles: ProcessToProfileData, options: AnalysisOptions) -> List[Finding]:
+        raise NotImplementedError
+
+
+def load_plugins(plugins_dir: str) -> Tuple[List[Analyzer], List[SampleSink]]:
+    """
+    Loads the analyzers & sample sinks defined by the Python modules in plugins_dir. Each module exposes a
+    get_analyzers() function returning its Analyzer instances, a get_sinks() function returning its SampleSink
+    instances, or both. Modules failing to load are logged and skipped.
+    """
+    analyzers: List[Analyzer] = []
+    sinks: List[SampleSink] = []
+    for path in sorted(Path(plugins_dir).glob("*.py")):
+        try:
+            spec = importlib.util.spec_from_file_location(f"gprofiler_analysis_plugin_{path.stem}", path)
+            assert spec is not None and spec.loader is not None, f"can't load {path}"
+            module = importlib.util.module_from_spec(spec)
+            spec.loader.exec_module(module)
+            if not hasattr(module, PLUGIN_ENTRY_POINT) and not hasattr(module, SINK_PLUGIN_ENTRY_POINT):
+                raise AttributeError(
+                    f"the plugin exposes neither {PLUGIN_ENTRY_POINT}() nor {SINK_PLUGIN_ENTRY_POINT}()"
+                )
+            plugin_analyzers = list(getattr(module, PLUGIN_ENTRY_POINT, list)())
+            for analyzer in plugin_analyzers:
+                if not isinstance(analyzer, Analyzer):
+                    raise TypeError(f"{PLUGIN_ENTRY_POINT}() returned a non-Analyzer object: {analyzer!r}")
+            plugin_sinks = list(getattr(module, SINK_PLUGIN_ENTRY_POINT, list)())
+            for sink in plugin_sinks:
+                if not isinstance(sink, SampleSink):
+                    raise TypeError(f"{SINK_PLUGIN_ENTRY_POINT}() returned a non-SampleSink object: {sink!r}")
+        except Exception:
+            logger.exception("Failed to load analysis plugin, skipping it", plugin=str(path))
+            continue
+
+        logger.info(
+            "Loaded analysis plugin",
+            plugin=str(path),
+            analyzers=[analyzer.name for analyzer in plugin_analyzers],
+            sinks=[sink.name for sink in plugin_sinks],
+        )
+        analyzers.extend(plugin_analyzers)
+        sinks.extend(plugin_sinks)
+
+    return analyzers, sinks
+
+
+def run_analyzers(
+    analyzers: Iterable[Analyzer], process_profiles: ProcessToProfileData, options: AnalysisOptions
+) -> List[Finding]:
+    """
+    Runs all analyzers, a failing analyzer doesn't prevent the others from reporting their findings.
+    """
+    findings: List[Finding] = []
+    for analyzer in analyzers:
+        try:
+            findings.extend(analyzer.analyze(process_profiles, options))
+        except Exception:
+            logger.exception("Analyzer failed", analyzer=analyzer.name)
+    return findings
@@ -0,0 +1,111 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+Compares the profiles of each session with a stored baseline profile (e.g the collapsed file of a known-good
+session), flagging the functions whose share of the samples of a process grew significantly - regressions become
+visible while the investigation is running, instead of after diffing flamegraphs.
+"""
+import json
+from collections import Counter
+from dataclasses import dataclass
+from pathlib import Path
+from typing import Dict, List, Tuple
+
+from gprofiler.analysis.analyzer import AnalysisOptions, Analyzer
+from gprofiler.analysis.findings import Finding
+from gprofiler.analysis.frames import frame_function
+from gprofiler.gprofiler_types import ProcessToProfileData, ProfilingErrorStack, StackToSampleCount
+from gprofiler.utils.collapsed_format import parse_one_collapsed
+
+DEFAULT_BASELINE_THRESHOLD = 5  # percentage points
+MAX_FINDINGS_PER_PROCESS = 5
+
+# process name -> function -> share (0-1) of the samples of the processes with that name
+BaselineShares = Dict[str, Dict[str, float]]
+
+
+def _function_shares(stacks: StackToSampleCount) -> Tuple[Dict[str, float], int]:
+    """
+    The share of the samples in which each function appears (its "total" share), and the number of samples.
+    """
+    total_samples = sum(stacks.values())
+    function_samples: Counter = Counter()
+    for stack, count in stacks.items():
+        # a function appearing multiple times in a stack (recursion) is counted once.
+        for function in {frame_function(frame) for frame in stack.split(";")[1:]}:
+            function_samples[function] += count
+    shares = {function: samples / total_samples for function, samples in function_samples.items()}
+    return shares, total_samples
+
+
+def load_baseline(path: str) -> BaselineShares:
+    """
+    Reads a baseline collapsed file, either written by gProfiler (whose stacks are prefixed with the application
+    metadata index & container name, per its header line) or a plain "comm;frame;frame count" collapsed file.
+    """
+    collapsed = Path(path).read_text(encoding="utf-8")
+    first_line = collapsed.split("\n", 1)[0]
+    extra_fields = 0
+    if first_line.startswith("#"):
+        header = json.loads(first_line[1:])
+        extra_fields = 2 if header.get("application_metadata_enabled") else 1
+
+    by_comm: Dict[str, StackToSampleCount] = {}
+    for stack, count in parse_one_collapsed(collapsed).items():
+        stack = stack.split(";", extra_fields)[-1]
+        comm = stack.split(";", 1)[0]
+        by_comm.setdefault(comm, Counter())[stack] += count
+    return {comm: _function_shares(stacks)[0] for comm, stacks in by_comm.items()}
+
+
+@dataclass(frozen=True)
+class BaselineAnalyzer(Analyzer):
+    baseline: BaselineShares
+    # the minimal increase (0-1) of a function's share over its baseline share to flag it
+    threshold: float
+    name: str = "baseline-regression"
+
+    def analyze(self, process_profiles: ProcessToProfileData, options: AnalysisOptions) -> List[Finding]:
+        findings = []
+        for pid, profile in process_profiles.items():
+            if ProfilingErrorStack.is_error_stack(profile.stacks) or not profile.stacks:
+                continue
+            comm = next(iter(profile.stacks)).split(";", 1)[0]
+            baseline = self.baseline.get(comm)
+            if baseline is None:
+                continue
+            shares, total_samples = _function_shares(profile.stacks)
+            increases = [
+                (share - baseline.get(function, 0.0), function, share)
+                for function, share in shares.items()
+                if share - baseline.get(function, 0.0) >= self.threshold
+            ]
+            for _, function, share in sorted(increases, reverse=True)[:MAX_FINDINGS_PER_PROCESS]:
+                baseline_share = baseline.get(function, 0.0)
+                findings.append(
+                    Finding(
+                        analyzer=self.name,
+                        message=f"{function} is in {share:.1%} of the samples of process {pid} ({comm}),"
+                        f" up from {baseline_share:.1%} in the baseline",
+                        pid=pid,
+                        comm=comm,
+                        samples=round(share * total_samples),
+                        total_samples=total_samples,
+                        # the finding is significant if the share is surely above the baseline share + threshold
+                        threshold=baseline_share + self.threshold,
+                    )
+                )
+        return findings
@@ -0,0 +1,101 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+Classifies Python frames by the kind of code they're in - the standard library, third-party packages
+(site-packages) or the application itself - answering "how much time is spent in our code".
+"""
+import fnmatch
+import re
+from functools import lru_cache
+from typing import Dict, Optional
+
+from gprofiler.analysis.frames import parse_python_frame
+from gprofiler.gprofiler_types import ProcessToProfileData, ProfilingErrorStack
+
+APP_CATEGORY = "app"
+THIRD_PARTY_CATEGORY = "third-party"
+STDLIB_CATEGORY = "stdlib"
+# samples without Python frames (other runtimes, native code)
+OTHER_CATEGORY = "other"
+FRAME_CATEGORIES = [APP_CATEGORY, THIRD_PARTY_CATEGORY, STDLIB_CATEGORY, OTHER_CATEGORY]
+
+_THIRD_PARTY_PATH_RE = re.compile(r"[\\/](?:site|dist)-packages[\\/]", re.IGNORECASE)
+# the standard library directory - "lib/pythonX.Y" on Linux, "Lib" on Windows.
+_STDLIB_PATH_RE = re.compile(r"(?:^|[\\/])(?:lib[\\/]python\d+(?:\.\d+)*|Lib)[\\/]", re.IGNORECASE)
+
+
+@lru_cache(maxsize=100_000)
+def classify_frame(frame: str) -> Optional[str]:
+    """
+    Returns the category of a Python frame, or None for frames of other runtimes.
+    """
+    python_frame = parse_python_frame(frame)
+    if python_frame is None:
+        return None
+    # the profilers annotate frames of installed packages with their package & version.
+    if python_frame.package is not None:
+        return STDLIB_CATEGORY if python_frame.package.startswith("standard-library") else THIRD_PARTY_CATEGORY
+    if _THIRD_PARTY_PATH_RE.search(python_frame.filename):
+        return THIRD_PARTY_CATEGORY
+    if _STDLIB_PATH_RE.search(python_frame.filename) or python_frame.filename.startswith("<frozen "):
+        return STDLIB_CATEGORY
+    return APP_CATEGORY
+
+
+def stack_category(stack: str) -> str:
+    """
+    Each sample is attributed to the category of its innermost Python frame, so the native frames called from
+    Python code (e.g C extensions, the interpreter itself) are attributed to the code calling them.
+    """
+    for frame in reversed(stack.split(";")[1:]):
+        category = classify_frame(frame)
+        if category is not None:
+            return category
+    return OTHER_CATEGORY
+
+
+def aggregate_frame_categories(process_profiles: ProcessToProfileData) -> Dict[str, int]:
+    samples = {category: 0 for category in FRAME_CATEGORIES}
+    for profile in process_profiles.values():
+        if ProfilingErrorStack.is_error_stack(profile.stacks):
+            continue
+        for stack, count in profile.stacks.items():
+            samples[stack_category(stack)] += count
+    return samples
+
+
+def _is_endpoint_frame(frame: str, endpoint: str) -> bool:
+    python_frame = parse_python_frame(frame)
+    return python_frame is not None and fnmatch.fnmatchcase(python_frame.function, endpoint)
+
+
+def first_party_share(process_profiles: ProcessToProfileData, endpoint: str) -> Optional[float]:
+    """
+    The share of the samples under the 'endpoint' Python function (a glob pattern, e.g "*.handle_checkout") that
+    are in first-party (application) code. None if there are no samples under it.
+    """
+    endpoint_samples = 0
+    app_samples = 0
+    for profile in process_profiles.values():
+        if ProfilingErrorStack.is_error_stack(profile.stacks):
+            continue
+        for stack, count in profile.stacks.items():
+            if not any(_is_endpoint_frame(frame, endpoint) for frame in stack.split(";")[1:]):
+                continue
+            endpoint_samples += count
+            if stack_category(stack) == APP_CATEGORY:
+                app_samples += count
+    return round(app_samples / endpoint_samples, 4) if endpoint_samples else None
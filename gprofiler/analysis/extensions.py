@@ -0,0 +1,94 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+Marks where Python code calls into native extension modules (pybind11, Cython, C extensions): a "[ext:<module>]"
+frame is inserted between the Python frame and the first native frame of the extension, so it's obvious which
+extension consumed the native time - even when its symbols are missing.
+The extension is recognized by the module (DSO) of the native frame, which the profilers include in frames without a
+symbol - e.g "(/usr/lib/python3/dist-packages/numpy/core/_multiarray_umath.cpython-38-x86_64-linux-gnu.so)" - and
+in all native frames with --insert-dso-name. Extensions installed as packages are named by their import path, e.g
+"numpy.core._multiarray_umath" and "pydantic_core._pydantic_core", so the package is attributed even when the module
+name itself is internal.
+"""
+import re
+from collections import Counter
+from typing import Optional
+
+from gprofiler.analysis.frames import parse_python_frame, strip_frame_suffix
+from gprofiler.gprofiler_types import ProcessToProfileData, ProfilingErrorStack, StackToSampleCount
+
+_FRAME_DSO_RE = re.compile(r"\((?P<dso>[^()]+)\)$")
+# e.g _multiarray_umath.cpython-38-x86_64-linux-gnu.so, _cffi_backend.abi3.so, _sqlite3.pyd, lib.cp311-win_amd64.pyd
+_EXTENSION_MODULE_RE = re.compile(
+    r"^(?P<name>[A-Za-z_]\w*)(?:\.(?P<tag>cpython-[\w-]+|pypy[\w-]*|abi3|cp\d+-[\w-]+))?\.(?P<ext>so|pyd)$",
+    re.IGNORECASE,
+)
+_PACKAGES_DIR_RE = re.compile(r"[\\/](?:site|dist)-packages[\\/]")
+_PACKAGE_NAME_RE = re.compile(r"^[A-Za-z_]\w*$")
+
+
+def _import_path(packages_path: str, name: str) -> str:
+    """
+    The import path of an extension module, from its path relative to the packages dir.
+    """
+    packages = re.split(r"[\\/]", packages_path)[:-1]
+    if not all(_PACKAGE_NAME_RE.match(package) for package in packages):
+        # e.g a ".libs" dir of vendored libraries - just the module name.
+        return name
+    return ".".join(packages + [name])
+
+
+def extension_module(frame: str) -> Optional[str]:
+    """
+    The name of the extension module of a native frame, or None if it's not in one (or its module is unknown).
+    """
+    m = _FRAME_DSO_RE.search(strip_frame_suffix(frame))
+    if m is None:
+        return None
+    dso = m.group("dso")
+    module = _EXTENSION_MODULE_RE.match(re.split(r"[\\/]", dso)[-1])
+    if module is None:
+        return None
+    # the innermost packages dir, in case of a virtualenv inside another's packages
+    packages_dirs = list(_PACKAGES_DIR_RE.finditer(dso))
+    # a plain "name.so" might be any shared library, unless it's installed as a Python package.
+    if module.group("tag") is None and module.group("ext").lower() == "so" and not packages_dirs:
+        return None
+    if not packages_dirs:
+        return module.group("name")
+    return _import_path(dso[packages_dirs[-1].end() :], module.group("name"))
+
+
+def _insert_boundaries(stack: str) -> str:
+    frames = stack.split(";")
+    result = frames[:2]
+    for previous, frame in zip(frames[1:], frames[2:]):
+        if parse_python_frame(previous) is not None and parse_python_frame(frame) is None:
+            module = extension_module(frame)
+            if module is not None:
+                result.append(f"[ext:{module}]")
+        result.append(frame)
+    return ";".join(result)
+
+
+def insert_extension_boundaries(profiles: ProcessToProfileData) -> None:
+    for profile in profiles.values():
+        if ProfilingErrorStack.is_error_stack(profile.stacks):
+            continue
+        stacks: StackToSampleCount = Counter()
+        for stack, count in profile.stacks.items():
+            stacks[_insert_boundaries(stack)] += count
+        profile.stacks = stacks
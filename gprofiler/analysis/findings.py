@@ -0,0 +1,122 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+import math
+from dataclasses import asdict, dataclass, field
+from typing import Any, Dict, List, Optional, Tuple
+
+from gprofiler.analysis.imports import MAX_IMPORTED_MODULES, ImportSamples
+
+# the z-score of the confidence intervals of the shares in the findings (95%)
+CONFIDENCE_Z = 1.96
+
+
+def share_confidence_interval(samples: int, total_samples: int, z: float = CONFIDENCE_Z) -> Tuple[float, float]:
+    """
+    The Wilson score interval of the share 'samples' / 'total_samples', treating each sample as an independent
+    draw. Unlike the normal approximation, it stays meaningful for the few samples of short sessions & shares near 0
+    or 1.
+    """
+    if total_samples == 0:
+        return 0.0, 1.0
+    share = samples / total_samples
+    denominator = 1 + z**2 / total_samples
+    center = (share + z**2 / (2 * total_samples)) / denominator
+    margin = z * math.sqrt(share * (1 - share) / total_samples + z**2 / (4 * total_samples**2)) / denominator
+    return max(0.0, center - margin), min(1.0, center + margin)
+
+
+@dataclass
+class CallSite:
+    # the frame calling into the flagged code, and the number of samples collected under it.
+    frame: str
+    samples: int
+    # the frames leading to the flagged code, from the root.
+    call_path: List[str] = field(default_factory=list)
+
+
+@dataclass
+class Finding:
+    """
+    A single result of an analysis pass, as included in the summary report.
+    """
+
+    analyzer: str
+    message: str
+    pid: int
+    comm: str
+    samples: int
+    total_samples: int
+    call_sites: List[CallSite] = field(default_factory=list)
+    # the share above which the analyzer flags processes, if it has one
+    threshold: Optional[float] = None
+
+    @property
+    def share(self) -> float:
+        return self.samples / self.total_samples if self.total_samples else 0.0
+
+    @property
+    def share_interval(self) -> Tuple[float, float]:
+        return share_confidence_interval(self.samples, self.total_samples)
+
+    @property
+    def low_confidence(self) -> bool:
+        """
+        Whether the share might be under the threshold, and the finding just noise of a short capture.
+        """
+        return self.threshold is not None and self.share_interval[0] < self.threshold
+
+    def to_dict(self) -> Dict[str, Any]:
+        low, high = self.share_interval
+        return {
+            **asdict(self),
+            "share": round(self.share, 4),
+            "share_interval": [round(low, 4), round(high, 4)],
+            "low_confidence": self.low_confidence,
+        }
+
+
+def make_summary_report(
+    findings: List[Finding],
+    frame_categories: Dict[str, int],
+    unknown_frames: Dict[str, int],
+    imports: ImportSamples,
+    start_time: str,
+    end_time: str,
+    schema_version: int,
+) -> Dict[str, Any]:
+    total_samples = sum(frame_categories.values())
+
+    def share(samples: int) -> float:
+        return round(samples / total_samples, 4) if total_samples else 0.0
+
+    return {
+        "schema_version": schema_version,
+        "start_time": start_time,
+        "end_time": end_time,
+        "findings": [finding.to_dict() for finding in findings],
+        "frame_categories": {
+            category: {"samples": samples, "share": share(samples)} for category, samples in frame_categories.items()
+        },
+        # the number of samples with frames the profilers couldn't resolve, per reason
+        "unknown_frames": unknown_frames,
+        "imports": {
+            **{phase: {"samples": samples, "share": share(samples)} for phase, samples in imports.phases.items()},
+            "modules": [
+                {"module": module, "samples": samples, "share": share(samples)}
+                for module, samples in imports.modules.most_common(MAX_IMPORTED_MODULES)
+            ],
+        },
+    }
@@ -0,0 +1,88 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+import re
+from dataclasses import dataclass
+from functools import lru_cache
+from typing import Iterable, Optional, Pattern, Tuple
+
+# Matches Python frames, as emitted by py-spy & PyPerf (see "Frame format" in the README), e.g:
+#   emit (/usr/lib/python3.8/logging/__init__.py:1084 [standard-library==3.8.10])_[p]
+# the filename part is matched lazily up to ".py:" so that Windows paths (C:\...) are parsed correctly.
+_PYTHON_FRAME_RE = re.compile(
+    r"^(?P<function>.*?) \((?P<filename>[^\(\)]+?\.py):(?P<line>\d+)(?: \[(?P<package>[^\]]*)\])?\)(?:_\[p\])?$"
+)
+
+# the runtime annotation suffix of frames, e.g "_[p]" (Python), "_[pn]" (Python native), "_[k]" (kernel)
+_FRAME_SUFFIX_RE = re.compile(r"_\[[a-z]+\]$")
+
+
+@dataclass
+class PythonFrame:
+    function: str
+    filename: str
+    line: int
+    package: Optional[str]
+
+
+def parse_python_frame(frame: str) -> Optional[PythonFrame]:
+    """
+    Parses a Python frame. Returns None for frames of other runtimes (native, kernel, Java, ...)
+    """
+    m = _PYTHON_FRAME_RE.match(frame)
+    if m is None:
+        return None
+    return PythonFrame(m.group("function"), m.group("filename"), int(m.group("line")), m.group("package"))
+
+
+def strip_frame_suffix(frame: str) -> str:
+    return _FRAME_SUFFIX_RE.sub("", frame)
+
+
+def frame_function(frame: str) -> str:
+    """
+    The function of a frame, regardless of the line: "function (filename)" for Python frames, the frame without
+    its runtime annotation suffix for others.
+    """
+    python_frame = parse_python_frame(frame)
+    if python_frame is not None:
+        return f"{python_frame.function} ({python_frame.filename})"
+    return strip_frame_suffix(frame)
+
+
+@lru_cache(maxsize=None)
+def _stdlib_modules_path_re(modules: Tuple[str, ...]) -> Pattern:
+    # a module file (lib/python3.8/re.py) or any file of a package (lib/python3.8/logging/handlers.py), under
+    # the standard library directory - "lib/pythonX.Y" on Linux, "Lib" on Windows.
+    alternatives = "|".join(re.escape(module) for module in modules)
+    return re.compile(
+        rf"(?:^|[\\/])(?:lib[\\/]python\d+(?:\.\d+)*|Lib)[\\/](?:{alternatives})(?:[\\/][^\\/]+)?\.py$",
+        re.IGNORECASE,
+    )
+
+
+def is_stdlib_module_frame(frame: str, modules: Iterable[str]) -> bool:
+    """
+    Is this a Python frame of one of the given standard library modules?
+    """
+    python_frame = parse_python_frame(frame)
+    if python_frame is None:
+        return False
+
+    modules_tuple = tuple(modules)
+    # PyPerf emits the qualified function name (module.function) so the module can be checked directly.
+    if any(python_frame.function.startswith(f"{module}.") for module in modules_tuple):
+        return True
+    return _stdlib_modules_path_re(modules_tuple).search(python_frame.filename) is not None
@@ -0,0 +1,105 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+Flags processes spending a large share of their samples under specific standard library modules - excessive
+logging, regex compilation on hot paths, (un)pickling etc are frequent (and easily fixable) CPU sinks.
+"""
+
+from collections import Counter
+from dataclasses import dataclass
+from typing import List, Tuple
+
+from gprofiler.analysis.analyzer import AnalysisOptions, Analyzer
+from gprofiler.analysis.findings import CallSite, Finding
+from gprofiler.analysis.frames import is_stdlib_module_frame
+from gprofiler.gprofiler_types import ProcessToProfileData, ProfilingErrorStack
+
+MAX_CALL_SITES = 5
+
+
+@dataclass(frozen=True)
+class ModuleHotspot(Analyzer):
+    name: str
+    # the standard library modules (or packages) whose frames are counted.
+    modules: Tuple[str, ...]
+    # describes the flagged code in the finding message, e.g "in the logging module".
+    description: str
+
+    def analyze(self, process_profiles: ProcessToProfileData, options: AnalysisOptions) -> List[Finding]:
+        return find_module_hotspots(process_profiles, self, options.hotspot_threshold)
+
+
+BUILTIN_HOTSPOTS = (
+    ModuleHotspot("logging-hotspot", ("logging",), "in the logging module"),
+    # sre_compile / sre_parse are the regex compiler, "re" is a package since 3.11.
+    ModuleHotspot("regex-hotspot", ("re", "sre_compile", "sre_parse"), "compiling & matching regular expressions"),
+    ModuleHotspot("pickle-hotspot", ("pickle", "copyreg", "_compat_pickle"), "pickling & unpickling"),
+    ModuleHotspot("json-hotspot", ("json",), "encoding & decoding JSON"),
+)
+
+
+def find_module_hotspots(
+    process_profiles: ProcessToProfileData, hotspot: ModuleHotspot, threshold: float
+) -> List[Finding]:
+    """
+    :param threshold: minimal share (0-1) of the process samples that must be under the hotspot modules to flag
+                      the process.
+    """
+    findings = []
+    for pid, profile in process_profiles.items():
+        if ProfilingErrorStack.is_error_stack(profile.stacks):
+            continue
+
+        total_samples = sum(profile.stacks.values())
+        if total_samples == 0:
+            continue
+
+        comm = ""
+        hotspot_samples = 0
+        call_paths: Counter = Counter()
+        for stack, count in profile.stacks.items():
+            frames = stack.split(";")
+            comm = frames[0]
+            # frames are ordered from the root, so the first matching frame is where the application called
+            # into the module, and the frames preceding it are the call path responsible.
+            for index, frame in enumerate(frames[1:], start=1):
+                if is_stdlib_module_frame(frame, hotspot.modules):
+                    hotspot_samples += count
+                    call_paths[tuple(frames[1:index])] += count
+                    break
+
+        if hotspot_samples == 0 or hotspot_samples / total_samples < threshold:
+            continue
+
+        findings.append(
+            Finding(
+                analyzer=hotspot.name,
+                message=f"{hotspot_samples / total_samples:.1%} of the samples of process {pid} ({comm})"
+                f" are spent {hotspot.description}",
+                pid=pid,
+                comm=comm,
+                samples=hotspot_samples,
+                total_samples=total_samples,
+                call_sites=[
+                    # stacks entering the module right at the root have no calling frame.
+                    CallSite(call_path[-1] if call_path else comm, samples, list(call_path))
+                    for call_path, samples in call_paths.most_common(MAX_CALL_SITES)
+                ],
+                threshold=threshold,
+            )
+        )
+
+    return findings
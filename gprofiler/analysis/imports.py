@@ -0,0 +1,98 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+Breaks down the time Python spends importing modules - a common hidden cost, e.g in workers forked or spawned often:
+* "import_lock" - waiting for (or holding) the import locks of importlib.
+* "module_init" - executing the module-level code of imported modules.
+* "import_machinery" - the rest of importlib: finding, loading & compiling the modules.
+Each sample is attributed by its innermost import-related frame, so the module-level code of a module imported by
+another module's module-level code is counted for the inner one.
+"""
+import re
+from collections import Counter
+from dataclasses import dataclass, field
+from typing import Dict, List, Optional
+
+from gprofiler.analysis.frames import parse_python_frame
+from gprofiler.gprofiler_types import ProcessToProfileData, ProfilingErrorStack
+
+IMPORT_LOCK_PHASE = "import_lock"
+MODULE_INIT_PHASE = "module_init"
+IMPORT_MACHINERY_PHASE = "import_machinery"
+IMPORT_PHASES = [IMPORT_LOCK_PHASE, MODULE_INIT_PHASE, IMPORT_MACHINERY_PHASE]
+
+# the modules with the most module-level code samples in the summary report
+MAX_IMPORTED_MODULES = 10
+
+# frames of importlib's bootstrap, frozen (e.g "<frozen importlib._bootstrap>") or not (Python 2, custom builds).
+_IMPORTLIB_FRAME_RE = re.compile(
+    r"^(?P<function>.*?) \((?:<frozen importlib\._bootstrap(?:_external)?>"
+    r"|[^()]*[\\/]importlib[\\/]_bootstrap(?:_external)?\.py):\d+\)(?:_\[p\])?$"
+)
+# the functions of importlib._bootstrap in which the import locks are taken
+_IMPORT_LOCK_FUNCTIONS = {"acquire", "_get_module_lock", "_lock_unlock_module", "__enter__"}
+_MODULE_FUNCTION = "<module>"
+
+
+@dataclass
+class ImportSamples:
+    phases: Dict[str, int] = field(default_factory=lambda: {phase: 0 for phase in IMPORT_PHASES})
+    # the module-level code samples, per file of the imported module
+    modules: Counter = field(default_factory=Counter)
+
+
+def _importlib_function(frame: str) -> Optional[str]:
+    m = _IMPORTLIB_FRAME_RE.match(frame)
+    return m.group("function") if m is not None else None
+
+
+def _add_stack(samples: ImportSamples, frames: List[str], count: int) -> None:
+    importing = False
+    phase: Optional[str] = None
+    module: Optional[str] = None
+    for frame in frames:
+        function = _importlib_function(frame)
+        if function is not None:
+            importing = True
+            phase = IMPORT_LOCK_PHASE if function in _IMPORT_LOCK_FUNCTIONS else IMPORT_MACHINERY_PHASE
+            continue
+        python_frame = parse_python_frame(frame)
+        if python_frame is None:
+            continue
+        # a "<module>" frame not called by importlib is of a script (the __main__ module), not of an import.
+        if importing and python_frame.function == _MODULE_FUNCTION:
+            phase = MODULE_INIT_PHASE
+            module = python_frame.filename
+        elif importing and phase != MODULE_INIT_PHASE:
+            # e.g importlib calling a custom finder / loader
+            phase = IMPORT_MACHINERY_PHASE
+
+    if phase is None:
+        return
+    samples.phases[phase] += count
+    if phase == MODULE_INIT_PHASE:
+        assert module is not None
+        samples.modules[module] += count
+
+
+def aggregate_imports(process_profiles: ProcessToProfileData) -> ImportSamples:
+    samples = ImportSamples()
+    for profile in process_profiles.values():
+        if ProfilingErrorStack.is_error_stack(profile.stacks):
+            continue
+        for stack, count in profile.stacks.items():
+            _add_stack(samples, stack.split(";")[1:], count)
+    return samples
@@ -0,0 +1,80 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+Keeps an inventory of the Python functions ever observed in the samples, across all sessions of a (possibly very
+long) run. Functions which never show up are candidates for dead code - without instrumenting the application.
+"""
+
+import json
+import os
+from pathlib import Path
+from typing import Any, Dict
+
+from gprofiler.analysis.frames import parse_python_frame
+from gprofiler.gprofiler_types import ProcessToProfileData, ProfilingErrorStack
+from gprofiler.utils.fs import write_file_restricted
+
+INVENTORY_FORMAT_VERSION = 1
+
+
+class CodeInventory:
+    def __init__(self, path: str, world_readable: bool = False) -> None:
+        self._path = Path(path)
+        self._world_readable = world_readable
+        # filename -> function -> {"first_seen", "last_seen", "samples"}
+        self._functions: Dict[str, Dict[str, Dict[str, Any]]] = {}
+        if self._path.exists():
+            # continue the inventory of previous runs
+            self._functions = json.loads(self._path.read_text(encoding="utf-8"))["functions"]
+
+    @property
+    def path(self) -> str:
+        return str(self._path)
+
+    @property
+    def functions_count(self) -> int:
+        return sum(len(functions) for functions in self._functions.values())
+
+    def update(self, process_profiles: ProcessToProfileData, timestamp: str) -> None:
+        for profile in process_profiles.values():
+            if ProfilingErrorStack.is_error_stack(profile.stacks):
+                continue
+
+            for stack, count in profile.stacks.items():
+                # a function appearing multiple times in a stack (recursion) is counted once.
+                seen = set()
+                for frame in stack.split(";")[1:]:
+                    python_frame = parse_python_frame(frame)
+                    if python_frame is None:
+                        continue
+                    seen.add((python_frame.filename, python_frame.function))
+
+                for filename, function in seen:
+                    entry = self._functions.setdefault(filename, {}).setdefault(
+                        function, {"first_seen": timestamp, "last_seen": timestamp, "samples": 0}
+                    )
+                    entry["last_seen"] = timestamp
+                    entry["samples"] += count
+
+    def save(self) -> None:
+        tmp_path = self._path.with_name(self._path.name + ".tmp")
+        write_file_restricted(
+            tmp_path,
+            json.dumps({"version": INVENTORY_FORMAT_VERSION, "functions": self._functions}).encode("utf-8"),
+            self._world_readable,
+        )
+        # replace atomically, so readers (and the next run) never see a partially written inventory.
+        os.replace(tmp_path, self._path)
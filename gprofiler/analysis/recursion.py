@@ -0,0 +1,81 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+Collapses the repetition cycles of deep recursive stacks (thousands of repeated frames) into synthetic "frame xN"
+frames, to keep the output sizes and the flamegraph rendering manageable. E.g "a;b;a;b;a;b;c" becomes
+"a x3;b x3;c".
+"""
+from collections import Counter
+from typing import List
+
+from gprofiler.gprofiler_types import ProcessToProfileData, ProfilingErrorStack, StackToSampleCount
+
+DEFAULT_COLLAPSE_RECURSION_DEPTH = 256
+# the longest cycle looked for, e.g a recursion through 8 functions
+MAX_CYCLE_LENGTH = 8
+# a cycle is collapsed only if it repeats at least that many times
+MIN_CYCLE_REPEATS = 2
+
+
+def _cycle_repeats(frames: List[str], start: int, length: int) -> int:
+    cycle = frames[start : start + length]
+    repeats = 1
+    while frames[start + repeats * length : start + (repeats + 1) * length] == cycle:
+        repeats += 1
+    return repeats
+
+
+def collapse_cycles(frames: List[str]) -> List[str]:
+    result = []
+    i = 0
+    while i < len(frames):
+        best_length, best_repeats = 1, 1
+        for length in range(1, min(MAX_CYCLE_LENGTH, (len(frames) - i) // MIN_CYCLE_REPEATS) + 1):
+            repeats = _cycle_repeats(frames, i, length)
+            # prefer the cycle covering the most frames, and the shortest one among those
+            if repeats >= MIN_CYCLE_REPEATS and repeats * length > best_repeats * best_length:
+                best_length, best_repeats = length, repeats
+        if best_repeats == 1:
+            result.append(frames[i])
+        else:
+            result.extend(f"{frame} x{best_repeats}" for frame in frames[i : i + best_length])
+        i += best_length * best_repeats
+    return result
+
+
+def collapse_recursive_stacks(stacks: StackToSampleCount, min_depth: int) -> StackToSampleCount:
+    result: StackToSampleCount = Counter()
+    for stack, count in stacks.items():
+        frames = stack.split(";")
+        # the first frame is the process comm
+        if len(frames) - 1 > min_depth:
+            stack = ";".join([frames[0]] + collapse_cycles(frames[1:]))
+        result[stack] += count
+    return result
+
+
+def collapse_recursion(profiles: ProcessToProfileData, min_depth: int) -> int:
+    """
+    Collapses the cycles of the stacks of 'profiles' deeper than 'min_depth' frames. Returns the number of stacks
+    which were collapsed.
+    """
+    collapsed = 0
+    for profile in profiles.values():
+        if ProfilingErrorStack.is_error_stack(profile.stacks):
+            continue
+        collapsed += sum(1 for stack in profile.stacks if stack.count(";") > min_depth)
+        profile.stacks = collapse_recursive_stacks(profile.stacks, min_depth)
+    return collapsed
@@ -0,0 +1,177 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+Sample sinks are invoked at the end of every aggregation window (profiling session) with a summary of it, for
+lightweight real-time reactions (alerting, anomaly detection) from the agent itself.
+"""
+import datetime
+from abc import ABC, abstractmethod
+from collections import Counter
+from dataclasses import dataclass, field
+from typing import Dict, Iterable, List, Optional
+
+from gprofiler.analysis.findings import Finding
+from gprofiler.analysis.frames import frame_function
+from gprofiler.gprofiler_types import ProcessToProfileData, ProfilingErrorStack
+from gprofiler.log import get_logger_adapter
+
+logger = get_logger_adapter(__name__)
+
+# the function each plugin module may expose, returning the sinks it defines.
+SINK_PLUGIN_ENTRY_POINT = "get_sinks"
+MAX_SUMMARY_FUNCTIONS = 10
+
+
+@dataclass
+class FunctionShare:
+    function: str
+    # the "self" share (0-1) of the window's samples in which the function is the innermost frame
+    share: float
+    # its share in the previous window, None in the first window
+    previous_share: Optional[float]
+
+    @property
+    def delta(self) -> float:
+        return self.share - (self.previous_share or 0.0)
+
+
+@dataclass
+class WindowSummary:
+    start_time: datetime.datetime
+    end_time: datetime.datetime
+    total_samples: int
+    # function -> its share, of all functions sampled in the window
+    function_shares: Dict[str, float]
+    # the functions with the largest shares, largest first
+    top_functions: List[FunctionShare]
+    # the functions whose share changed the most since the previous window, largest change first
+    top_deltas: List[FunctionShare]
+    # profiling error frame (e.g "[Profiling error: exception]") -> the number of processes that got it
+    error_counts: Dict[str, int]
+    # the profilers which failed altogether
+    failed_profilers: List[str] = field(default_factory=list)
+    # the findings of the analyzers
+    findings: List[Finding] = field(default_factory=list)
+
+
+class SampleSink(ABC):
+    """
+    Receives the summary of every aggregation window.
+    """
+
+    name: str
+
+    @abstractmethod
+    def on_window(self, summary: WindowSummary) -> None:
+        raise NotImplementedError
+
+    def close(self) -> None:
+        """
+        Called when gProfiler stops, e.g to flush what the sink has pending.
+        """
+        pass
+
+
+def function_self_shares(process_profiles: ProcessToProfileData) -> Dict[str, float]:
+    """
+    The share of the samples (of all processes) in which each function is the innermost frame.
+    """
+    function_samples: Counter = Counter()
+    for profile in process_profiles.values():
+        if ProfilingErrorStack.is_error_stack(profile.stacks):
+            continue
+        for stack, count in profile.stacks.items():
+            function_samples[frame_function(stack.rsplit(";", 1)[-1])] += count
+    total_samples = sum(function_samples.values())
+    return {function: samples / total_samples for function, samples in function_samples.items()}
+
+
+def make_window_summary(
+    process_profiles: ProcessToProfileData,
+    previous_shares: Optional[Dict[str, float]],
+    start_time: datetime.datetime,
+    end_time: datetime.datetime,
+    failed_profilers: Iterable[str] = (),
+    findings: Iterable[Finding] = (),
+) -> WindowSummary:
+    shares = function_self_shares(process_profiles)
+    functions = [
+        FunctionShare(function, share, None if previous_shares is None else previous_shares.get(function, 0.0))
+        for function, share in shares.items()
+    ]
+    if previous_shares is not None:
+        # functions which disappeared since the previous window
+        functions += [
+            FunctionShare(function, 0.0, share) for function, share in previous_shares.items() if function not in shares
+        ]
+
+    error_counts: Counter = Counter()
+    for profile in process_profiles.values():
+        if ProfilingErrorStack.is_error_stack(profile.stacks):
+            error_counts[next(iter(profile.stacks)).split(";", 1)[1]] += 1
+
+    return WindowSummary(
+        start_time=start_time,
+        end_time=end_time,
+        total_samples=sum(
+            sum(profile.stacks.values())
+            for profile in process_profiles.values()
+            if not ProfilingErrorStack.is_error_stack(profile.stacks)
+        ),
+        function_shares=shares,
+        top_functions=sorted(functions, key=lambda f: f.share, reverse=True)[:MAX_SUMMARY_FUNCTIONS],
+        top_deltas=(
+            sorted(functions, key=lambda f: abs(f.delta), reverse=True)[:MAX_SUMMARY_FUNCTIONS]
+            if previous_shares is not None
+            else []
+        ),
+        error_counts=dict(error_counts),
+        failed_profilers=list(failed_profilers),
+        findings=list(findings),
+    )
+
+
+@dataclass(frozen=True)
+class FunctionShareAlertSink(SampleSink):
+    """
+    Logs an alert when a single function exceeds a share of the samples of a window.
+    """
+
+    # minimal share (0-1) of the window's samples in which a function is the innermost frame to alert on it.
+    threshold: float
+    name: str = "function-share-alert"
+
+    def on_window(self, summary: WindowSummary) -> None:
+        for function_share in summary.top_functions:
+            if function_share.share < self.threshold:
+                break
+            logger.warning(
+                f"Alert: {function_share.function} is the innermost frame of {function_share.share:.1%} of the"
+                f" samples, over the {self.threshold:.0%} threshold",
+                sink=self.name,
+                previous_share=function_share.previous_share,
+            )
+
+
+def run_sinks(sinks: Iterable[SampleSink], summary: WindowSummary) -> None:
+    """
+    Invokes all sinks, a failing sink doesn't prevent the others from being invoked.
+    """
+    for sink in sinks:
+        try:
+            sink.on_window(summary)
+        except Exception:
+            logger.exception("Sample sink failed", sink=sink.name)
@@ -0,0 +1,62 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+Tracks when each stack was first seen during a continuous run, so the stacks which appeared since an event (e.g a
+deploy) can be told apart from those that were already there.
+The profilers aggregate their samples, so a stack's first-seen time is the end time of the first session it was
+sampled in.
+"""
+import os
+from collections import Counter
+from typing import Dict, Optional
+
+from gprofiler.gprofiler_types import StackToSampleCount
+
+
+class StackAges:
+    def __init__(self) -> None:
+        # stack -> first-seen time (seconds since the epoch)
+        self._first_seen: Dict[str, float] = {}
+
+    def __len__(self) -> int:
+        return len(self._first_seen)
+
+    def update(self, stacks: StackToSampleCount, session_end_time: float) -> None:
+        for stack in stacks:
+            self._first_seen.setdefault(stack, session_end_time)
+
+    def first_seen(self, stack: str) -> Optional[float]:
+        return self._first_seen.get(stack)
+
+    def stacks_since(self, stacks: StackToSampleCount, marker_time: float) -> StackToSampleCount:
+        """
+        The stacks of 'stacks' first seen after 'marker_time'. Those first seen in the session during which the
+        marker was set are included, as it's unknown whether they appeared before or after it.
+        """
+        return Counter(
+            {stack: count for stack, count in stacks.items() if self._first_seen.get(stack, marker_time) > marker_time}
+        )
+
+
+def read_marker_time(path: str) -> Optional[float]:
+    """
+    The time of the event marked by the file 'path' (its modification time, so it's set with "touch"), or None if
+    it doesn't exist.
+    """
+    try:
+        return os.stat(path).st_mtime
+    except FileNotFoundError:
+        return None
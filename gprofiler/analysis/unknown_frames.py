@@ -0,0 +1,80 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+Frames which the profilers couldn't resolve are replaced by a placeholder that tells why ("[unknown:<reason>]" by
+default), and the samples with such frames are counted per reason, to help debugging sparse flamegraphs.
+"""
+import re
+from collections import Counter
+from typing import Dict, Optional
+
+from gprofiler.analysis.frames import strip_frame_suffix
+from gprofiler.gprofiler_types import ProcessToProfileData, ProfilingErrorStack, StackToSampleCount
+
+DEFAULT_UNKNOWN_FRAME_PLACEHOLDER = "[unknown:{reason}]"
+# the frame perf & PyPerf emit for addresses they couldn't resolve
+UNKNOWN_FRAME = "[unknown]"
+
+# the address isn't in any module known to the profiler (e.g JIT code, or a stack that was unwound incorrectly)
+NO_MAPPING_REASON = "no-mapping"
+# the address is in a known module, but without a symbol for it (e.g a stripped binary); the profiler emits the
+# module name instead, e.g "(/usr/lib/libfoo.so)", so these frames are kept as is and only counted.
+NO_SYMBOL_REASON = "no-symbol"
+UNKNOWN_FRAME_REASONS = [NO_MAPPING_REASON, NO_SYMBOL_REASON]
+
+_MODULE_ONLY_FRAME_RE = re.compile(r"^\([^()]+\)$")
+
+
+def unknown_frame_reason(frame: str) -> Optional[str]:
+    frame = strip_frame_suffix(frame)
+    if frame == UNKNOWN_FRAME:
+        return NO_MAPPING_REASON
+    if _MODULE_ONLY_FRAME_RE.match(frame):
+        return NO_SYMBOL_REASON
+    return None
+
+
+def _replace_unknown_frames_in_stacks(
+    stacks: StackToSampleCount, placeholder: str, reason_samples: Dict[str, int]
+) -> StackToSampleCount:
+    no_mapping_frame = placeholder.format(reason=NO_MAPPING_REASON)
+    result: StackToSampleCount = Counter()
+    for stack, count in stacks.items():
+        frames = stack.split(";")
+        reasons = set()
+        # the first frame is the process comm
+        for i, frame in enumerate(frames[1:], start=1):
+            reason = unknown_frame_reason(frame)
+            if reason is None:
+                continue
+            reasons.add(reason)
+            if reason == NO_MAPPING_REASON:
+                frames[i] = no_mapping_frame + frame[len(UNKNOWN_FRAME) :]  # keep the suffix, e.g "_[k]"
+        for reason in reasons:
+            reason_samples[reason] += count
+        result[";".join(frames)] += count
+    return result
+
+
+def replace_unknown_frames(profiles: ProcessToProfileData, placeholder: str, reason_samples: Dict[str, int]) -> None:
+    """
+    Replaces the unresolved frames of 'profiles' with 'placeholder', and adds the number of samples with unknown
+    frames per reason to 'reason_samples'.
+    """
+    for profile in profiles.values():
+        if ProfilingErrorStack.is_error_stack(profile.stacks):
+            continue
+        profile.stacks = _replace_unknown_frames_in_stacks(profile.stacks, placeholder, reason_samples)
@@ -0,0 +1,63 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+In wall-clock profiling, the samples of blocked threads are kept. py-spy doesn't report the state of the sampled
+threads, so the reason a thread is blocked is derived from its innermost Python frame - the standard library call
+it's waiting in - and appended to the stack as a "[wait:<reason>]" frame. It's a heuristic: the frames are matched by
+module (and function), so waits in native code whose innermost Python frame is the caller (e.g time.sleep) aren't
+annotated, and a thread running on the CPU inside one of those modules is annotated as waiting.
+"""
+from collections import Counter
+from typing import Optional, Tuple
+
+from gprofiler.analysis.frames import is_stdlib_module_frame, parse_python_frame
+from gprofiler.gprofiler_types import StackToSampleCount
+
+IO_WAIT_REASON = "io"
+LOCK_WAIT_REASON = "lock"
+SLEEP_WAIT_REASON = "sleep"
+
+# the standard library modules whose (innermost) frames mean waiting, per reason
+_WAIT_MODULES: Tuple[Tuple[str, Tuple[str, ...]], ...] = (
+    (IO_WAIT_REASON, ("selectors", "select", "socket", "ssl", "socketserver", "subprocess")),
+    (LOCK_WAIT_REASON, ("threading", "queue", "multiprocessing")),
+)
+# the standard library sleep functions (module, function) which are Python functions - time.sleep isn't
+_SLEEP_FUNCTIONS: Tuple[Tuple[str, str], ...] = (("asyncio", "sleep"),)
+
+
+def wait_reason(frame: str) -> Optional[str]:
+    python_frame = parse_python_frame(frame)
+    if python_frame is None:
+        return None
+    for module, function in _SLEEP_FUNCTIONS:
+        if python_frame.function in (function, f"{module}.{function}") and is_stdlib_module_frame(frame, (module,)):
+            return SLEEP_WAIT_REASON
+    for reason, modules in _WAIT_MODULES:
+        if is_stdlib_module_frame(frame, modules):
+            return reason
+    return None
+
+
+def annotate_wait_reasons(stacks: StackToSampleCount) -> StackToSampleCount:
+    result: StackToSampleCount = Counter()
+    for stack, count in stacks.items():
+        innermost_frame = stack.rsplit(";", 1)[-1]
+        reason = wait_reason(innermost_frame)
+        if reason is not None:
+            stack = f"{stack};[wait:{reason}]"
+        result[stack] += count
+    return result
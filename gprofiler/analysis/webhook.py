@@ -0,0 +1,122 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+A sample sink POSTing a JSON summary of every session to a webhook, for ChatOps notifications without an
+intermediate service.
+"""
+import time
+from dataclasses import dataclass, field
+from queue import Full, Queue
+from threading import Thread
+from typing import Any, Dict, Optional
+
+import requests
+
+from gprofiler.analysis.sinks import SampleSink, WindowSummary
+from gprofiler.log import get_logger_adapter
+from gprofiler.utils import get_iso8601_format_time
+
+logger = get_logger_adapter(__name__)
+
+WEBHOOK_TIMEOUT = 10  # seconds, of each POST
+# the summaries waiting to be POSTed - beyond that (the webhook is down, or slower than the sessions), new ones are
+# dropped.
+WEBHOOK_QUEUE_SIZE = 10
+# how long closing the sink waits for the pending summaries (e.g of the last session) to be POSTed
+WEBHOOK_CLOSE_TIMEOUT = 2 * WEBHOOK_TIMEOUT
+
+
+def make_webhook_payload(summary: WindowSummary, top_functions: int, identity: Dict[str, Any]) -> Dict[str, Any]:
+    payload: Dict[str, Any] = {
+        **identity,
+        "start_time": get_iso8601_format_time(summary.start_time),
+        "end_time": get_iso8601_format_time(summary.end_time),
+        "total_samples": summary.total_samples,
+        "error_counts": summary.error_counts,
+        "failed_profilers": summary.failed_profilers,
+        "findings": [finding.to_dict() for finding in summary.findings],
+    }
+    if top_functions > 0:
+        payload["top_functions"] = [
+            {"function": f.function, "share": round(f.share, 4)} for f in summary.top_functions[:top_functions]
+        ]
+    return payload
+
+
+@dataclass
+class WebhookSink(SampleSink):
+    """
+    POSTs from a background thread, so a slow or unreachable webhook doesn't delay the profiling sessions.
+    """
+
+    url: str
+    # POST only the summaries of sessions with findings
+    findings_only: bool = False
+    # the number of functions with the largest shares to include, 0 for none
+    top_functions: int = 0
+    # identifies the agent in the payloads, e.g the hostname & service name
+    identity: Dict[str, Any] = field(default_factory=dict)
+    name: str = "webhook"
+    _queue: "Queue[Optional[Dict[str, Any]]]" = field(
+        default_factory=lambda: Queue(WEBHOOK_QUEUE_SIZE), init=False, repr=False
+    )
+    _thread: Optional[Thread] = field(default=None, init=False, repr=False)
+
+    def on_window(self, summary: WindowSummary) -> None:
+        if self.findings_only and not summary.findings:
+            return
+
+        if self._thread is None:
+            self._thread = Thread(target=self._post_loop, name="webhook", daemon=True)
+            self._thread.start()
+        try:
+            self._queue.put_nowait(make_webhook_payload(summary, self.top_functions, self.identity))
+        except Full:
+            logger.warning("The webhook is falling behind, dropping the session summary", pending=self._queue.qsize())
+
+    def close(self) -> None:
+        if self._thread is None:
+            return
+        deadline = time.monotonic() + WEBHOOK_CLOSE_TIMEOUT
+        try:
+            # stops the thread after the pending summaries are POSTed
+            self._queue.put(None, timeout=WEBHOOK_CLOSE_TIMEOUT)
+        except Full:
+            pass
+        self._thread.join(max(deadline - time.monotonic(), 0))
+        if self._thread.is_alive():
+            # it's a daemon thread, it doesn't block the exit
+            logger.warning("Timed out POSTing the pending session summaries to the webhook, dropping them")
+        self._thread = None
+
+    def _post_loop(self) -> None:
+        while True:
+            payload = self._queue.get()
+            if payload is None:
+                return
+            try:
+                self._post(payload)
+            except Exception:
+                logger.exception("Webhook sink failed", sink=self.name)
+
+    def _post(self, payload: Dict[str, Any]) -> None:
+        try:
+            response = requests.post(self.url, json=payload, timeout=WEBHOOK_TIMEOUT)
+            response.raise_for_status()
+        except requests.RequestException as e:
+            # not logging the URL & the exception message (which includes it), webhook URLs often embed a secret.
+            status = e.response.status_code if e.response is not None else None
+            logger.warning(f"Failed to POST the session summary to the webhook: {type(e).__name__}", status=status)
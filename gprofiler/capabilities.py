@@ -0,0 +1,85 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+The capabilities manifest (--capabilities), letting orchestration feature-detect the installed gProfiler
+instead of parsing version strings.
+"""
+
+import argparse
+import json
+import platform
+from typing import Any, Dict, NoReturn, Optional, Sequence, Union
+
+from gprofiler import __version__
+from gprofiler.analysis.hotspots import BUILTIN_HOTSPOTS
+from gprofiler.consts import SUPPORTED_OUTPUT_SCHEMA_VERSIONS
+from gprofiler.metadata.system_metadata import get_arch
+from gprofiler.output_formats.registry import get_output_formats_registry
+from gprofiler.platform import is_windows
+from gprofiler.profilers.registry import get_profilers_registry
+
+# bump when keys are removed or change their meaning; adding keys is backward compatible.
+CAPABILITIES_SCHEMA_VERSION = 1
+
+PROFILING_MODES = ["cpu", "allocation", "none"]
+OUTPUT_FORMATS = ["collapsed", "flamegraph-html", "summary-json"]
+
+
+def get_capabilities() -> Dict[str, Any]:
+    arch = get_arch()
+    profilers = {}
+    for profiler_name, profiler_config in get_profilers_registry().items():
+        supported_archs = profiler_config.supported_windows_archs if is_windows() else profiler_config.supported_archs
+        profilers[profiler_name.lower()] = {
+            "supported": arch in supported_archs,
+            "modes": [mode for mode in profiler_config.possible_modes if mode != "none"],
+            "default_mode": profiler_config.default_mode,
+            "profiling_modes": profiler_config.supported_profiling_modes,
+            "max_frequency": getattr(profiler_config.profiler_class, "MAX_FREQUENCY", None),
+        }
+
+    return {
+        "schema_version": CAPABILITIES_SCHEMA_VERSION,
+        "version": __version__,
+        "os": platform.system().lower(),
+        "arch": arch,
+        "profiling_modes": PROFILING_MODES,
+        "profilers": profilers,
+        # PyPerf unwinds the native frames of Python processes, it's available only on Linux x86_64.
+        "python_native_unwinding": not is_windows() and arch == "x86_64",
+        "output_formats": OUTPUT_FORMATS + sorted(get_output_formats_registry()),
+        "output_schema_versions": SUPPORTED_OUTPUT_SCHEMA_VERSIONS,
+        "analyzers": [hotspot.name for hotspot in BUILTIN_HOTSPOTS],
+    }
+
+
+class CapabilitiesAction(argparse.Action):
+    """
+    Prints the capabilities manifest and exits, like the "version" action.
+    """
+
+    def __init__(self, option_strings: Sequence[str], dest: str = argparse.SUPPRESS, help: Optional[str] = None):
+        super().__init__(option_strings=option_strings, dest=dest, default=argparse.SUPPRESS, nargs=0, help=help)
+
+    def __call__(
+        self,
+        parser: argparse.ArgumentParser,
+        namespace: argparse.Namespace,
+        values: Union[str, Sequence[Any], None],
+        option_string: Optional[str] = None,
+    ) -> NoReturn:
+        print(json.dumps(get_capabilities(), indent=2))
+        parser.exit()
@@ -0,0 +1,74 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+gProfiler's settings are read from (lowest precedence first):
+1. Environment variables - GPROFILER_<OPTION>, e.g GPROFILER_PROFILING_FREQUENCY=11 for --profiling-frequency 11.
+2. Config files - /etc/gprofiler/config.ini, and the file given by --config.
+3. The command line.
+"""
+import os
+import sys
+from typing import Dict, Mapping, NoReturn, Optional, Sequence, Set
+
+import configargparse
+
+from gprofiler.exit_codes import EXIT_CONFIG_ERROR
+
+ENV_VAR_PREFIX = "gprofiler_"
+
+# the source keys of configargparse's get_source_to_settings_dict()
+_CONFIG_FILE_SOURCE_PREFIX = "config_file"
+
+
+class ArgumentParser(configargparse.ArgumentParser):
+    """
+    Exits with EXIT_CONFIG_ERROR on invalid arguments, rather than argparse's 2 (which is EXIT_PARTIAL_SUCCESS).
+    The subcommand parsers are of the class of their parent, so they exit the same.
+    """
+
+    def error(self, message: str) -> NoReturn:
+        self.print_usage(sys.stderr)
+        self.exit(EXIT_CONFIG_ERROR, f"{self.prog}: error: {message}\n")
+
+
+def _config_file_env_vars(parser: configargparse.ArgumentParser) -> Set[str]:
+    """
+    The environment variables of the options set by the config files in the last parse.
+    """
+    env_vars = set()
+    for source, settings in parser.get_source_to_settings_dict().items():
+        if not source.startswith(_CONFIG_FILE_SOURCE_PREFIX):
+            continue
+        for action, _ in settings.values():
+            if action is not None and getattr(action, "env_var", None):
+                env_vars.add(action.env_var)
+    return env_vars
+
+
+def parse_config_args(
+    parser: configargparse.ArgumentParser,
+    args: Optional[Sequence[str]] = None,
+    env_vars: Optional[Mapping[str, str]] = None,
+) -> configargparse.Namespace:
+    """
+    Parses the settings in the order of precedence above. configargparse itself prefers the environment variables
+    over the config files, so the arguments are parsed once to find the options set in the config files, and parsed
+    again without the environment variables of those options.
+    """
+    environ: Dict[str, str] = dict(os.environ if env_vars is None else env_vars)
+    parser.parse_known_args(args, env_vars=environ)
+    overridden = _config_file_env_vars(parser)
+    return parser.parse_args(args, env_vars={key: value for key, value in environ.items() if key not in overridden})
@@ -1,2 +1,7 @@
 CPU_PROFILING_MODE = "cpu"
 DEFAULT_PROFILING_MODE = CPU_PROFILING_MODE
+
+# version of the schema of gProfiler's machine-readable outputs (the collapsed file metadata header, the summary
+# report). Bump on incompatible changes, keeping the previous versions writable via --schema-version.
+OUTPUT_SCHEMA_VERSION = 1
+SUPPORTED_OUTPUT_SCHEMA_VERSIONS = [1]
@@ -48,6 +48,15 @@ class ContainerNamesClient:
         self._pid_to_container_name_cache.clear()
         self._current_container_names.clear()
 
+    def compact_cache(self) -> int:
+        """
+        Drops the container ID -> name cache, which keeps the entries of exited containers until the next refresh.
+        It is refilled lazily for the containers still running. Returns the number of removed entries.
+        """
+        removed = len(self._container_id_to_name_cache)
+        self._container_id_to_name_cache.clear()
+        return removed
+
     @property
     def container_names(self) -> List[str]:
         return list(self._current_container_names)
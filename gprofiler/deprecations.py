@@ -0,0 +1,84 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+Reports the use of deprecated options in a machine-readable form - a JSON line on stderr per deprecated option
+(besides the log warning), and in the profile metadata - so orchestration tools can detect & migrate configurations
+fleet-wide before the options are removed.
+"""
+import json
+import sys
+from dataclasses import dataclass
+from typing import Any, Dict, List, Optional
+
+from gprofiler.log import get_logger_adapter
+
+logger = get_logger_adapter(__name__)
+
+# the key of the JSON lines written to stderr
+STDERR_DEPRECATION_KEY = "gprofiler_deprecation"
+
+
+@dataclass(frozen=True)
+class DeprecatedOption:
+    option: str
+    dest: str
+    # the option is considered used if its value differs from this
+    default: Any
+    # the version in which the option stopped having an effect, if it did
+    removed_in: Optional[str] = None
+    # the option to use instead, if any
+    replacement: Optional[str] = None
+
+    @property
+    def message(self) -> str:
+        message = f"{self.option} is deprecated"
+        if self.removed_in is not None:
+            message += f" and removed in version {self.removed_in}"
+        if self.replacement is not None:
+            message += f", use {self.replacement} instead"
+        return message
+
+
+DEPRECATED_OPTIONS = (
+    DeprecatedOption("--spark-sample-period", "spark_sample_period", 120, removed_in="1.42.0"),
+    DeprecatedOption("--collect-spark-metrics", "collect_spark_metrics", False, removed_in="1.42.0"),
+    DeprecatedOption(
+        "--databricks-job-name-as-service-name", "databricks_job_name_as_service_name", False, removed_in="1.49.0"
+    ),
+    DeprecatedOption("--glogger-server", "glogger_server", None, replacement="--api-server"),
+)
+
+
+def find_deprecated_options(user_args: Dict[str, Any]) -> List[Dict[str, Any]]:
+    """
+    The deprecated options used, as dicts of "option", "removed_in", "replacement" and "message".
+    """
+    return [
+        {
+            "option": option.option,
+            "removed_in": option.removed_in,
+            "replacement": option.replacement,
+            "message": option.message,
+        }
+        for option in DEPRECATED_OPTIONS
+        if option.dest in user_args and user_args[option.dest] != option.default
+    ]
+
+
+def report_deprecated_options(deprecations: List[Dict[str, Any]]) -> None:
+    for deprecation in deprecations:
+        logger.warning(deprecation["message"])
+        print(json.dumps({STDERR_DEPRECATION_KEY: deprecation}), file=sys.stderr)
@@ -0,0 +1,164 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+Merges the collapsed files of many profiling sessions into coarser windows (e.g 1 minute sessions into 1 hour
+windows), for long-term local retention at a low storage cost. The output files are named like the session files
+(profile_<window end time>.col), so they can be downsampled again into even coarser windows.
+"""
+
+import datetime
+import json
+import os
+import re
+from collections import Counter
+from dataclasses import dataclass
+from pathlib import Path
+from typing import Any, Dict, List, Optional, Tuple
+
+from gprofiler.consts import SUPPORTED_OUTPUT_SCHEMA_VERSIONS
+from gprofiler.gprofiler_types import StackToSampleCount
+from gprofiler.utils import get_iso8601_format_time
+from gprofiler.utils.collapsed_format import parse_one_collapsed
+from gprofiler.utils.fs import escape_filename, write_file_restricted
+
+# session files are named by their end time, with "-" instead of ":" on Windows.
+_SESSION_FILE_RE = re.compile(
+    r"^profile_(?P<date>\d{4}-\d{2}-\d{2})T(?P<hour>\d{2})[:-](?P<minute>\d{2})[:-](?P<second>\d{2})\.col$"
+)
+# blobs which describe a single session, and are dropped from the downsampled files.
+_SESSION_ONLY_KEYS = ["flamegraph_html", "htmlblob", "hwmetrics"]
+
+
+class DownsampleError(Exception):
+    pass
+
+
+@dataclass
+class WindowFile:
+    path: Path
+    end_time: datetime.datetime
+    header: Dict[str, Any]
+    stacks: StackToSampleCount
+
+
+def session_file_end_time(path: Path) -> Optional[datetime.datetime]:
+    m = _SESSION_FILE_RE.match(path.name)
+    if m is None:
+        return None
+    return datetime.datetime.fromisoformat(m.expand(r"\g<date>T\g<hour>:\g<minute>:\g<second>"))
+
+
+def read_window_file(path: Path, end_time: datetime.datetime) -> WindowFile:
+    collapsed = path.read_text(encoding="utf-8")
+    first_line = collapsed.split("\n", 1)[0]
+    if not first_line.startswith("#"):
+        raise DownsampleError(f"{path} has no metadata header")
+    header = json.loads(first_line[1:])
+    schema_version = header.get("schema_version", SUPPORTED_OUTPUT_SCHEMA_VERSIONS[0])
+    if schema_version not in SUPPORTED_OUTPUT_SCHEMA_VERSIONS:
+        raise DownsampleError(f"Unsupported schema version {schema_version} of {path}")
+    return WindowFile(path, end_time, header, parse_one_collapsed(collapsed))
+
+
+def _window_end(end_time: datetime.datetime, window: int) -> datetime.datetime:
+    # windows are aligned to multiples of their length since the epoch, so consecutive runs produce the same windows.
+    epoch = datetime.datetime(1970, 1, 1)
+    seconds = int((end_time - epoch).total_seconds())
+    return epoch + datetime.timedelta(seconds=-(-seconds // window) * window)
+
+
+def _merge_metrics(files: List[WindowFile]) -> Dict[str, Any]:
+    """
+    Numeric metrics (e.g the CPU & memory averages) are averaged over the files, others are taken from the last one.
+    """
+    merged: Dict[str, Any] = dict(files[-1].header.get("metrics") or {})
+    for key in merged:
+        values = [f.header["metrics"].get(key) for f in files if f.header.get("metrics")]
+        numbers = [v for v in values if isinstance(v, (int, float)) and not isinstance(v, bool)]
+        if numbers and len(numbers) == len(values):
+            merged[key] = sum(numbers) / len(numbers)
+    return merged
+
+
+def merge_window_files(files: List[WindowFile], window: int, end_time: datetime.datetime) -> str:
+    """
+    Merges the files of a window, summing the weights of identical stacks. The application metadata indices at the
+    start of the stacks are remapped into the merged application metadata list.
+    """
+    files = sorted(files, key=lambda f: f.end_time)
+    application_metadata: List[Optional[Dict]] = []
+    stacks: StackToSampleCount = Counter()
+    windows = 0
+    for f in files:
+        file_app_metadata = f.header.get("application_metadata") or []
+        remap_indices = f.header.get("application_metadata_enabled") and file_app_metadata
+        for stack, count in f.stacks.items():
+            if remap_indices:
+                idx, sep, rest = stack.partition(";")
+                if idx.isdigit() and int(idx) < len(file_app_metadata):
+                    app_metadata = file_app_metadata[int(idx)]
+                    if app_metadata not in application_metadata:
+                        application_metadata.append(app_metadata)
+                    stack = f"{application_metadata.index(app_metadata)}{sep}{rest}"
+            stacks[stack] += count
+        windows += f.header.get("downsample", {}).get("windows", 1)
+
+    header = dict(files[-1].header)
+    for key in _SESSION_ONLY_KEYS:
+        header[key] = None
+    if application_metadata:
+        header["application_metadata"] = application_metadata
+    header["metrics"] = _merge_metrics(files)
+    header["downsample"] = {
+        "window": window,
+        "end_time": get_iso8601_format_time(end_time),
+        # the number of original sessions, including those of downsampled input files
+        "windows": windows,
+    }
+    lines = ["# " + json.dumps(header)] + [f"{stack} {count}" for stack, count in stacks.items()]
+    return "\n".join(lines) + "\n"
+
+
+def downsample_directory(
+    input_dir: str, output_dir: str, window: int, now: datetime.datetime, world_readable: bool = False
+) -> Tuple[List[str], List[str]]:
+    """
+    Downsamples the session files of 'input_dir' into windows of 'window' seconds in 'output_dir'. Windows which
+    haven't ended yet (by 'now') are left for a later run. The written files are accessible to their owner only,
+    unless 'world_readable'.
+    Returns the paths of the written files, and of the input files merged into them.
+    """
+    windows: Dict[datetime.datetime, List[WindowFile]] = {}
+    for path in sorted(Path(input_dir).iterdir()):
+        end_time = session_file_end_time(path)
+        if end_time is None or path.is_symlink():  # skip last_profile.col & co.
+            continue
+        window_end = _window_end(end_time, window)
+        if window_end > now:
+            continue
+        windows.setdefault(window_end, []).append(read_window_file(path, end_time))
+
+    written = []
+    merged = []
+    for window_end, files in sorted(windows.items()):
+        end_ts = escape_filename(get_iso8601_format_time(window_end))
+        output_path = os.path.join(output_dir, f"profile_{end_ts}.col")
+        write_file_restricted(
+            output_path, merge_window_files(files, window, window_end).encode("utf-8"), world_readable
+        )
+        written.append(output_path)
+        merged.extend(str(f.path) for f in files)
+    return written, merged
@@ -26,6 +26,7 @@ import requests
 from gprofiler.dynamic_profiling_management.ad_hoc import AdhocProfilerSlot
 from gprofiler.dynamic_profiling_management.command_control import CommandManager, ProfilingCommand
 from gprofiler.dynamic_profiling_management.continuous import ContinuousProfilerSlot
+from gprofiler.feature_flags import feature_flags
 from gprofiler.metadata.system_metadata import get_hostname
 
 logger = logging.getLogger(__name__)
@@ -94,6 +95,8 @@ class HeartbeatClient:
 
             if response.status_code == 200:
                 result = response.json()
+                if "kill_switches" in result:
+                    feature_flags.set_control_switches({"kill_switches": result["kill_switches"]})
                 if result.get("success") and result.get("profiling_command"):
                     logger.info(f"Received profiling command from server: {result.get('command_id')}")
                     return cast(Dict[str, Any], result)
@@ -0,0 +1,120 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+Machine-readable records of the per-process profiling failures, so they can be aggregated (e.g error rates per kind
+across a fleet) without parsing the tracebacks in the logs. Each failure is classified into one of ERROR_KINDS, and
+written as a line of JSON to the --error-records file.
+"""
+import datetime
+import json
+import re
+from dataclasses import asdict, dataclass
+from threading import Lock
+from typing import Any, Dict, Optional
+
+from psutil import AccessDenied, NoSuchProcess, ZombieProcess
+
+from gprofiler.exceptions import CalledProcessError, ProcessStoppedException
+from gprofiler.log import get_logger_adapter
+from gprofiler.utils import get_iso8601_format_time
+from gprofiler.utils.fs import append_file_restricted
+
+logger = get_logger_adapter(__name__)
+
+ERROR_RECORDS_FORMAT_VERSION = 1
+# messages (e.g including the output of a profiler's executable) are truncated to this length
+MAX_MESSAGE_LENGTH = 1000
+
+# the profiler isn't permitted to read or attach to the process
+ACCESS_DENIED = "access-denied"
+# the runtime (or its version) isn't supported by the profiler
+VERSION_UNSUPPORTED = "version-unsupported"
+# the process exited while it was profiled
+PROCESS_EXITED = "process-exited"
+# the profiler failed to read the memory of the process, e.g the runtime's structures changed while being read
+READ_FAULT = "read-fault"
+# any other failure
+OTHER = "other"
+ERROR_KINDS = [ACCESS_DENIED, VERSION_UNSUPPORTED, PROCESS_EXITED, READ_FAULT, OTHER]
+
+# as printed by the profilers' executables (py-spy, rbspy, async-profiler...) - checked in order.
+_OUTPUT_PATTERNS = [
+    (PROCESS_EXITED, re.compile(r"No such process|process (?:has )?exited", re.IGNORECASE)),
+    (ACCESS_DENIED, re.compile(r"Permission denied|Operation not permitted|Access is denied", re.IGNORECASE)),
+    (VERSION_UNSUPPORTED, re.compile(r"unsupported (?:\w+ )?version|not supported", re.IGNORECASE)),
+    (READ_FAULT, re.compile(r"Bad address|Failed to (?:copy|read)|process_vm_readv|Input/output error", re.IGNORECASE)),
+]
+
+
+def classify_message(message: str) -> str:
+    """
+    Classifies an error message - e.g the output of a profiler's executable, or the reason of an error marker stack.
+    """
+    for kind, pattern in _OUTPUT_PATTERNS:
+        if pattern.search(message):
+            return kind
+    return OTHER
+
+
+def classify_exception(exception: BaseException) -> str:
+    if isinstance(exception, (NoSuchProcess, ZombieProcess, ProcessStoppedException)):
+        return PROCESS_EXITED
+    if isinstance(exception, (AccessDenied, PermissionError)):
+        return ACCESS_DENIED
+    if isinstance(exception, CalledProcessError):
+        return classify_message(exception.stderr or "")
+    return classify_message(str(exception))
+
+
+@dataclass
+class ErrorRecord:
+    profiler: str
+    pid: int
+    comm: str
+    kind: str
+    # the exception type, if the failure was an exception
+    exception: Optional[str]
+    message: str
+
+    def to_dict(self) -> Dict[str, Any]:
+        return asdict(self)
+
+
+class ErrorRecordsWriter:
+    """
+    Appends the records to a file (JSON lines). Profilers profile processes in parallel, so writes are serialized.
+    """
+
+    def __init__(self, path: str, world_readable: bool = False) -> None:
+        self._path = path
+        self._world_readable = world_readable
+        self._lock = Lock()
+
+    def write(self, record: ErrorRecord) -> None:
+        line = json.dumps(
+            {
+                "version": ERROR_RECORDS_FORMAT_VERSION,
+                "time": get_iso8601_format_time(datetime.datetime.utcnow()),
+                **record.to_dict(),
+                "message": record.message[:MAX_MESSAGE_LENGTH],
+            }
+        )
+        try:
+            with self._lock:
+                # it's accessible to its owner only (unless --world-readable), like the output files
+                append_file_restricted(self._path, (line + "\n").encode("utf-8"), self._world_readable)
+        except OSError:
+            logger.warning("Failed to write an error record", path=self._path, exc_info=True)
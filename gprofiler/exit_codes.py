@@ -0,0 +1,32 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+The exit codes of gProfiler, the same in all subcommands, so wrappers can tell the outcomes apart.
+They're documented in the README ("Exit codes") - they're an interface, don't change the existing ones.
+"""
+
+# everything went well
+EXIT_SUCCESS = 0
+# an unexpected error, or one that's not in any of the categories below
+EXIT_ERROR = 1
+# gProfiler ran to the end, but some of it failed (e.g a profiler failed in a session, or an upload failed)
+EXIT_PARTIAL_SUCCESS = 2
+# the processes to profile can't be profiled (e.g none of the --pids are alive)
+EXIT_UNSUPPORTED_TARGET = 3
+# gProfiler lacks the permissions it needs (e.g not running as root)
+EXIT_PERMISSION = 4
+# invalid arguments / configuration. argparse's own exit code (2) is replaced by this one.
+EXIT_CONFIG_ERROR = 5
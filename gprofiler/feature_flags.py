@@ -0,0 +1,159 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+Kill switches that disable risky subsystems at runtime, so issues can be mitigated on a fleet without redeploying.
+Kill switches come from a JSON file (--feature-flags-file, re-read when it changes) and from the heartbeat server
+(the "kill_switches" key of the heartbeat response), in the same format:
+
+    {"kill_switches": [{"feature": "native-unwinding"}, {"feature": "application-metadata", "process": "java"}]}
+
+"process" is a glob matched against the process name or executable path, and defaults to "*" (all processes).
+"""
+
+import fnmatch
+import json
+import os
+from dataclasses import dataclass
+from threading import Lock
+from typing import Any, Iterable, List, Optional
+
+from granulate_utils.linux.process import process_exe
+from psutil import AccessDenied, NoSuchProcess, Process, ZombieProcess
+
+from gprofiler.log import get_logger_adapter
+
+logger = get_logger_adapter(__name__)
+
+# PyPerf's native (eBPF) unwinding of Python stacks; when disabled, Python is profiled with py-spy.
+NATIVE_UNWINDING = "native-unwinding"
+# the per-runtime application metadata readers (versions, JVM flags, ...)
+APPLICATION_METADATA = "application-metadata"
+# the application identifiers (gunicorn / uwsgi / celery / java -jar ... appids)
+APPLICATION_IDENTIFIERS = "application-identifiers"
+FEATURES = [NATIVE_UNWINDING, APPLICATION_METADATA, APPLICATION_IDENTIFIERS]
+
+
+class FeatureFlagsError(Exception):
+    pass
+
+
+@dataclass(frozen=True)
+class KillSwitch:
+    feature: str
+    process: str = "*"
+
+    def matches(self, process: Process) -> bool:
+        if self.process == "*":
+            return True
+        try:
+            return fnmatch.fnmatch(process.name(), self.process) or fnmatch.fnmatch(process_exe(process), self.process)
+        except (NoSuchProcess, ZombieProcess, AccessDenied, OSError):
+            # can't tell - err on the side of the mitigation
+            return True
+
+
+def parse_kill_switches(config: Any) -> List[KillSwitch]:
+    if not isinstance(config, dict) or not isinstance(config.get("kill_switches", []), list):
+        raise FeatureFlagsError('Expected an object with a "kill_switches" list')
+
+    switches = []
+    for entry in config.get("kill_switches", []):
+        if not isinstance(entry, dict) or entry.get("feature") not in FEATURES:
+            raise FeatureFlagsError(f"Invalid kill switch {entry!r}, the feature must be one of {FEATURES}")
+        process = entry.get("process", "*")
+        if not isinstance(process, str):
+            raise FeatureFlagsError(f"Invalid kill switch {entry!r}, the process must be a string")
+        switches.append(KillSwitch(entry["feature"], process))
+    return switches
+
+
+def read_feature_flags_file(path: str) -> List[KillSwitch]:
+    try:
+        with open(path) as f:
+            config = json.load(f)
+    except (OSError, ValueError) as e:
+        raise FeatureFlagsError(f"Failed to read {path}: {e}") from e
+    return parse_kill_switches(config)
+
+
+class FeatureFlags:
+    def __init__(self) -> None:
+        self._lock = Lock()
+        self._path: Optional[str] = None
+        self._mtime: Optional[float] = None
+        self._file_switches: List[KillSwitch] = []
+        self._control_switches: List[KillSwitch] = []
+
+    def set_file(self, path: Optional[str]) -> None:
+        with self._lock:
+            self._path = path
+            self._mtime = None
+            self._file_switches = []
+        self.reload_file()
+
+    def reload_file(self) -> None:
+        """
+        Re-reads the feature flags file if it has changed. If it can't be read, the previous kill switches are kept.
+        """
+        if self._path is None:
+            return
+        try:
+            mtime = os.stat(self._path).st_mtime
+            if mtime == self._mtime:
+                return
+            switches = read_feature_flags_file(self._path)
+        except (OSError, FeatureFlagsError):
+            logger.exception("Failed to reload the feature flags file, keeping the previous kill switches")
+            return
+
+        with self._lock:
+            if switches != self._file_switches:
+                logger.info("Kill switches changed", source=self._path, kill_switches=[str(s) for s in switches])
+            self._mtime = mtime
+            self._file_switches = switches
+
+    def set_control_switches(self, config: Any) -> None:
+        try:
+            switches = parse_kill_switches(config)
+        except FeatureFlagsError:
+            logger.exception("Received invalid kill switches from the server, keeping the previous ones")
+            return
+
+        with self._lock:
+            if switches != self._control_switches:
+                logger.info("Kill switches changed", source="server", kill_switches=[str(s) for s in switches])
+            self._control_switches = switches
+
+    def _switches(self, feature: str) -> List[KillSwitch]:
+        with self._lock:
+            return [s for s in self._file_switches + self._control_switches if s.feature == feature]
+
+    def is_disabled(self, feature: str, process: Process) -> bool:
+        return any(switch.matches(process) for switch in self._switches(feature))
+
+    def is_disabled_for_any(self, feature: str, processes: Iterable[Process]) -> bool:
+        """
+        For the host-wide subsystems (e.g PyPerf) - whether the feature is disabled for any of 'processes'.
+        """
+        switches = self._switches(feature)
+        if not switches:
+            return False
+        if any(switch.process == "*" for switch in switches):
+            return True
+        return any(switch.matches(process) for process in processes for switch in switches)
+
+
+feature_flags = FeatureFlags()
@@ -97,6 +97,13 @@ def integers_list(value_str: str) -> List[int]:
     return values
 
 
+def strings_list(value_str: str) -> List[str]:
+    values = [value.strip() for value in value_str.split(",") if value.strip()]
+    if not values:
+        raise configargparse.ArgumentTypeError("String list should be a comma separated list of values f.e. 1000,1001")
+    return values
+
+
 def integer_range(min_range: int, max_range: int) -> Callable[[str], int]:
     def integer_range_check(value_str: str) -> int:
         value = int(value_str)
@@ -13,6 +13,8 @@
 # See the License for the specific language governing permissions and
 # limitations under the License.
 #
+import codecs
+import io
 import logging
 import logging.handlers
 import os
@@ -20,7 +22,7 @@ import re
 import sys
 import time
 from logging import LogRecord
-from typing import Any, Dict, Mapping, Optional
+from typing import Any, Dict, Mapping, Optional, Tuple
 from urllib.parse import urlparse
 
 from glogger.extra_adapter import ExtraAdapter
@@ -28,6 +30,7 @@ from glogger.handler import BatchRequestsHandler
 from glogger.sender import AuthToken, Sender
 
 from gprofiler import __version__
+from gprofiler.platform import is_windows
 from gprofiler.state import get_state
 
 NO_SERVER_LOG_KEY = "no_server_log"
@@ -35,6 +38,7 @@ NO_SERVER_EXTRA_KEY = "no_extra_to_server"
 CYCLE_ID_KEY = "cycle_id"
 LOGGER_NAME_RE = re.compile(r"gprofiler(?:\..+)?")
 LOGS_FORMAT = "[%(asctime)s] %(levelname)s: %(name)s: %(message)s"
+CP_UTF8 = 65001
 
 
 def get_logger_adapter(logger_name: str) -> logging.LoggerAdapter:
@@ -86,10 +90,12 @@ class RemoteLogsHandler(BatchRequestsHandler):
         return super().emit(record)
 
     def get_metadata(self) -> Dict[str, str]:
-        from gprofiler.metadata.system_metadata import get_hostname_or_none
+        from gprofiler.metadata.system_metadata import get_boot_id_or_none, get_host_id_or_none, get_hostname_or_none
 
         state = get_state()
         hostname = get_hostname_or_none()
+        host_id = get_host_id_or_none()
+        boot_id = get_boot_id_or_none()
 
         # here we add fields which don't change during the lifetime of gProfiler.
         # fields that do change go in GProfilerExtraAdapter.get_extra().
@@ -100,6 +106,10 @@ class RemoteLogsHandler(BatchRequestsHandler):
         }
         if hostname is not None:
             metadata["hostname"] = hostname
+        if host_id is not None:
+            metadata["host_id"] = host_id
+        if boot_id is not None:
+            metadata["boot_id"] = boot_id
 
         return metadata
 
@@ -137,18 +147,93 @@ class GProfilerFormatter(_ExtraFormatter, _UTCFormatter):
     pass
 
 
+def parse_log_filter(spec: str) -> Tuple[Optional[int], Dict[str, int]]:
+    """
+    Parses a --log-filter spec, e.g "warning,profilers.python=debug,client=info": a comma-separated list of
+    <module>=<level>, optionally with a bare <level> for all other modules. Module names are the logger names, with
+    or without the "gprofiler." prefix, and apply to their submodules as well.
+    Returns the default level (if given) and the level of each logger.
+    """
+    default_level = None
+    levels = {}
+    for directive in filter(None, (d.strip() for d in spec.split(","))):
+        module, sep, level_name = directive.rpartition("=")
+        level = logging.getLevelName(level_name.strip().upper())
+        if not isinstance(level, int):
+            raise ValueError(f"unknown log level {level_name!r}")
+        if not sep:
+            default_level = level
+            continue
+        module = module.strip()
+        if not module:
+            raise ValueError(f"missing a module name in {directive!r}")
+        levels[module if LOGGER_NAME_RE.fullmatch(module) else f"gprofiler.{module}"] = level
+    return default_level, levels
+
+
+class LogLevelFilter(logging.Filter):
+    """
+    Filters records by the level of the most specific module (logger name prefix) they match.
+    """
+
+    def __init__(self, default_level: int, levels: Dict[str, int]):
+        super().__init__()
+        self._default_level = default_level
+        self._levels = levels
+
+    @property
+    def min_level(self) -> int:
+        return min([self._default_level, *self._levels.values()])
+
+    def filter(self, record: LogRecord) -> bool:
+        name = record.name
+        while True:
+            level = self._levels.get(name)
+            if level is not None:
+                return record.levelno >= level
+            if "." not in name:
+                return record.levelno >= self._default_level
+            name = name.rsplit(".", 1)[0]
+
+
+def setup_console_encoding(encoding: Optional[str]) -> None:
+    """
+    Frames (and thus logs) may contain non-ASCII identifiers which the console encoding (e.g a legacy Windows
+    code page) can't represent: escape those instead of failing to write the record.
+    If encoding is given, the console output is switched to it (on Windows, the console code page as well).
+    """
+    if not isinstance(sys.stdout, io.TextIOWrapper):
+        return
+
+    if encoding is not None and is_windows() and codecs.lookup(encoding).name == "utf-8":
+        import ctypes
+
+        ctypes.windll.kernel32.SetConsoleOutputCP(CP_UTF8)  # type: ignore[attr-defined]
+
+    sys.stdout.reconfigure(encoding=encoding, errors="backslashreplace")
+
+
 def initial_root_logger_setup(
     stream_level: int,
     log_file_path: str,
     rotate_max_bytes: int,
     rotate_backup_count: int,
     remote_logs_handler: Optional[RemoteLogsHandler],
+    console_encoding: Optional[str] = None,
+    log_filter: Optional[str] = None,
 ) -> logging.LoggerAdapter:
     logger_adapter = get_logger_adapter("gprofiler")
     logger_adapter.setLevel(logging.DEBUG)
 
+    setup_console_encoding(console_encoding)
     stream_handler = logging.StreamHandler(stream=sys.stdout)
-    stream_handler.setLevel(stream_level)
+    if log_filter:
+        default_level, levels = parse_log_filter(log_filter)
+        level_filter = LogLevelFilter(default_level if default_level is not None else stream_level, levels)
+        stream_handler.setLevel(level_filter.min_level)
+        stream_handler.addFilter(level_filter)
+    else:
+        stream_handler.setLevel(stream_level)
     stream_handler.setFormatter(GProfilerFormatter(LOGS_FORMAT))
     logger_adapter.logger.addHandler(stream_handler)
 
@@ -157,6 +242,8 @@ def initial_root_logger_setup(
         log_file_path,
         maxBytes=rotate_max_bytes,
         backupCount=rotate_backup_count,
+        # don't depend on the locale encoding (e.g cp1252 on Windows), frames may contain any identifier.
+        encoding="utf-8",
     )
     file_handler.setLevel(logging.DEBUG)
     file_handler.setFormatter(GProfilerFormatter(LOGS_FORMAT))
@@ -13,20 +13,27 @@
 # See the License for the specific language governing permissions and
 # limitations under the License.
 #
+import codecs
 import concurrent.futures
 import datetime
+import json
 import logging
 import logging.config
 import logging.handlers
 import os
+import random
+import re
 import shutil
+import subprocess
 import sys
+import threading
 import time
 import traceback
 from pathlib import Path
 from threading import Event
 from types import TracebackType
-from typing import Iterable, List, Optional, Type, cast
+from typing import Any, Dict, Iterable, List, Optional, Type, cast
+from urllib.parse import urlparse
 
 import configargparse
 import humanfriendly
@@ -37,27 +44,90 @@ from psutil import NoSuchProcess, Process, process_iter
 from requests import RequestException, Timeout
 
 from gprofiler import __version__
+from gprofiler.analysis.analyzer import AnalysisOptions, Analyzer, load_plugins, run_analyzers
+from gprofiler.analysis.baseline import DEFAULT_BASELINE_THRESHOLD, BaselineAnalyzer, load_baseline
+from gprofiler.analysis.categories import aggregate_frame_categories, first_party_share
+from gprofiler.analysis.extensions import insert_extension_boundaries
+from gprofiler.analysis.findings import Finding, make_summary_report
+from gprofiler.analysis.hotspots import BUILTIN_HOTSPOTS
+from gprofiler.analysis.imports import ImportSamples, aggregate_imports
+from gprofiler.analysis.inventory import CodeInventory
+from gprofiler.analysis.recursion import DEFAULT_COLLAPSE_RECURSION_DEPTH, collapse_recursion
+from gprofiler.analysis.sinks import (
+    MAX_SUMMARY_FUNCTIONS,
+    FunctionShareAlertSink,
+    SampleSink,
+    make_window_summary,
+    run_sinks,
+)
+from gprofiler.analysis.stack_ages import StackAges, read_marker_time
+from gprofiler.analysis.unknown_frames import (
+    DEFAULT_UNKNOWN_FRAME_PLACEHOLDER,
+    UNKNOWN_FRAME_REASONS,
+    replace_unknown_frames,
+)
+from gprofiler.analysis.webhook import WebhookSink
+from gprofiler.capabilities import CapabilitiesAction
 from gprofiler.client import (
     DEFAULT_API_SERVER_ADDRESS,
     DEFAULT_PROFILER_SERVER_ADDRESS,
     DEFAULT_UPLOAD_TIMEOUT,
     ProfilerAPIClient,
 )
-from gprofiler.consts import CPU_PROFILING_MODE
+from gprofiler.config import ENV_VAR_PREFIX, ArgumentParser, parse_config_args
+from gprofiler.consts import CPU_PROFILING_MODE, OUTPUT_SCHEMA_VERSION, SUPPORTED_OUTPUT_SCHEMA_VERSIONS
 from gprofiler.containers_client import ContainerNamesClient
+from gprofiler.deprecations import find_deprecated_options, report_deprecated_options
 from gprofiler.diagnostics import log_diagnostics, set_diagnostics
+from gprofiler.downsample import DownsampleError, downsample_directory
+from gprofiler.dry_run import make_dry_run_report
 from gprofiler.dynamic_profiling_management.heartbeat import DynamicGProfilerManager, HeartbeatClient
+from gprofiler.error_records import ErrorRecordsWriter
 from gprofiler.exceptions import APIError, NoProfilersEnabledError
-from gprofiler.gprofiler_types import ProcessToProfileData, UserArgs, integers_list, positive_integer
+from gprofiler.exit_codes import (
+    EXIT_CONFIG_ERROR,
+    EXIT_ERROR,
+    EXIT_PARTIAL_SUCCESS,
+    EXIT_PERMISSION,
+    EXIT_SUCCESS,
+    EXIT_UNSUPPORTED_TARGET,
+)
+from gprofiler.feature_flags import FEATURES, FeatureFlagsError, feature_flags, read_feature_flags_file
+from gprofiler.gprofiler_types import (
+    ProcessToProfileData,
+    ProfilingErrorStack,
+    UserArgs,
+    integer_range,
+    integers_list,
+    nonnegative_integer,
+    positive_integer,
+    strings_list,
+)
 from gprofiler.hw_metrics import HWMetricsMonitor, HWMetricsMonitorBase, NoopHWMetricsMonitor
-from gprofiler.log import RemoteLogsHandler, initial_root_logger_setup
-from gprofiler.merge import concatenate_from_external_file, concatenate_profiles, merge_profiles
+from gprofiler.log import RemoteLogsHandler, initial_root_logger_setup, parse_log_filter
+from gprofiler.merge import (
+    concatenate_from_external_file,
+    concatenate_profiles,
+    merge_profiles,
+    normalize_sample_weights,
+    seed_samples_random,
+)
 from gprofiler.metadata import ProfileMetadata
 from gprofiler.metadata.application_identifiers import ApplicationIdentifiers
+from gprofiler.metadata.application_metadata import ApplicationMetadata
+from gprofiler.metadata.detection_cache import DetectionCache
 from gprofiler.metadata.enrichment import EnrichmentOptions
 from gprofiler.metadata.external_metadata import ExternalMetadataStaleError, read_external_metadata
 from gprofiler.metadata.metadata_collector import get_current_metadata, get_static_metadata
-from gprofiler.metadata.system_metadata import get_hostname, get_run_mode, get_static_system_info
+from gprofiler.metadata.system_metadata import (
+    get_boot_id_or_none,
+    get_host_id_or_none,
+    get_hostname,
+    get_run_mode,
+    get_static_system_info,
+)
+from gprofiler.output_formats.output_format import OutputContext, OutputFormatOptionError
+from gprofiler.output_formats.registry import get_output_formats_registry, parse_format_options
 from gprofiler.platform import is_aarch64, is_linux, is_windows
 from gprofiler.profiler_state import ProfilerState
 from gprofiler.profilers.factory import get_profilers
@@ -69,6 +139,7 @@ from gprofiler.system_metrics import Metrics, NoopSystemMetricsMonitor, SystemMe
 from gprofiler.usage_loggers import CgroupsUsageLogger, NoopUsageLogger, UsageLoggerInterface
 from gprofiler.utils import (
     TEMPORARY_STORAGE_PATH,
+    age,
     atomically_symlink,
     get_iso8601_format_time,
     grab_gprofiler_mutex,
@@ -77,8 +148,15 @@ from gprofiler.utils import (
     run_process,
     setup_signals,
 )
-from gprofiler.utils.fs import escape_filename, mkdir_owned_root_wrapper
+from gprofiler.utils.collapsed_format import insert_frame_after_comm, parse_one_collapsed
+from gprofiler.utils.fs import escape_filename, mkdir_owned_root_wrapper, restrict_to_owner, write_file_restricted
+from gprofiler.utils.manifest import MANIFEST_SUFFIX, find_session_manifest, make_manifest, verify_file
+from gprofiler.utils.ntp import query_clock_offset
+from gprofiler.utils.process_filter import ProcessFilter, rootless_restrictions
+from gprofiler.utils.process_tree import get_process_tree
 from gprofiler.utils.proxy import get_https_proxy
+from gprofiler.utils.shared_memory import HEADER_SIZE as SHARED_MEMORY_HEADER_SIZE
+from gprofiler.utils.shared_memory import SharedMemoryPublisher
 
 if is_linux():
     from gprofiler.utils.linux import disable_core_files
@@ -95,10 +173,19 @@ DEFAULT_PID_FILE = "/var/run/gprofiler.pid"
 DEFAULT_PROFILING_DURATION = datetime.timedelta(seconds=60).seconds
 DEFAULT_SAMPLING_FREQUENCY = 11
 DEFAULT_ALLOC_INTERVAL = "2mb"
+DEFAULT_ANALYSIS_HOTSPOT_THRESHOLD = 10
+DEFAULT_CACHE_COMPACTION_INTERVAL = 60 * 60
+DEFAULT_SHARED_MEMORY_SIZE = "16MiB"
+WARMUP_FRAME = "[warmup]"
 
 DIAGNOSTICS_INTERVAL_S = 15 * 60
 
 UPLOAD_FILE_SUBCOMMAND = "upload-file"
+PROFILE_COMMAND_SUBCOMMAND = "profile-command"
+DOWNSAMPLE_SUBCOMMAND = "downsample"
+DEFAULT_DOWNSAMPLE_WINDOW = "5m"
+# batch jobs are short, so profile them at a higher rate to have enough samples
+PROFILE_COMMAND_FREQUENCY = 99
 
 
 class GProfiler:
@@ -145,6 +232,8 @@ class GProfiler:
         self._spawn_time = time.time()
         self._last_diagnostics = 0.0
         self._gpid = ""
+        # set when a part of a session fails (a profiler, an upload) - the run is then only a partial success
+        self._had_errors = False
         self._controller_process = controller_process
         self._duration = duration
         self._external_metadata_path = external_metadata_path
@@ -152,6 +241,89 @@ class GProfiler:
         self._collect_hw_metrics = collect_hw_metrics
         self._perfspect_path = perfspect_path
         self._perfspect_duration = perfspect_duration
+        # the frequency all sample counts are normalized to, see normalize_sample_weights().
+        self._sampling_frequency: Optional[int] = (
+            user_args.get("frequency") if profiling_mode == CPU_PROFILING_MODE else None
+        )
+        self._clock_sync_server: Optional[str] = user_args.get("clock_sync_server")
+        # the deprecated options given, recorded in the metadata
+        self._deprecations = find_deprecated_options(user_args)
+        # without an explicit seed, pick one - it's recorded in the metadata so the run can be reproduced.
+        self._seed = user_args.get("seed")
+        if self._seed is None:
+            self._seed = random.randrange(2**32)
+        seed_samples_random(self._seed)
+        self._cache_compaction_interval = int(
+            user_args.get("cache_compaction_interval", DEFAULT_CACHE_COMPACTION_INTERVAL)
+        )
+        self._last_cache_compaction = time.monotonic()
+        self._analysis = bool(user_args.get("analysis", True))
+        hotspot_threshold = int(user_args.get("analysis_hotspot_threshold") or DEFAULT_ANALYSIS_HOTSPOT_THRESHOLD)
+        self._analysis_options = AnalysisOptions(hotspot_threshold=hotspot_threshold / 100)
+        self._warmup = int(user_args.get("warmup") or 0)
+        self._schema_version = int(user_args.get("schema_version") or OUTPUT_SCHEMA_VERSION)
+        self._world_readable = bool(user_args.get("world_readable"))
+        self._flamegraph_color_by_category = bool(user_args.get("flamegraph_color_by_category"))
+        self._unknown_frame_placeholder: str = (
+            user_args.get("unknown_frame_placeholder") or DEFAULT_UNKNOWN_FRAME_PLACEHOLDER
+        )
+        self._extension_frames = bool(user_args.get("extension_frames", True))
+        self._collapse_recursion_depth = int(
+            user_args.get("collapse_recursion_depth", DEFAULT_COLLAPSE_RECURSION_DEPTH) or 0
+        )
+        self._first_party_share_endpoints: List[str] = user_args.get("first_party_share_endpoints") or []
+        output_format_names: List[str] = user_args.get("output_formats") or []
+        output_format_options = parse_format_options(user_args.get("format_opts") or [], output_format_names)
+        self._output_formats = [
+            get_output_formats_registry()[name](output_format_options[name]) for name in output_format_names
+        ]
+        feature_flags.set_file(user_args.get("feature_flags_file"))
+        shared_memory_output = user_args.get("shared_memory_output")
+        shared_memory_size = humanfriendly.parse_size(
+            user_args.get("shared_memory_size") or DEFAULT_SHARED_MEMORY_SIZE, binary=True
+        )
+        self._shared_memory = (
+            SharedMemoryPublisher(shared_memory_output, shared_memory_size, self._world_readable)
+            if shared_memory_output
+            else None
+        )
+        encrypt_output = user_args.get("encrypt_output")
+        self._encryption_recipients = age.read_recipients_file(encrypt_output) if encrypt_output else None
+        self._code_inventory = (
+            CodeInventory(user_args["code_inventory"], self._world_readable)
+            if user_args.get("code_inventory") is not None
+            else None
+        )
+        self._detection_cache = (
+            DetectionCache(user_args["detection_cache"], self._world_readable)
+            if user_args.get("detection_cache") is not None
+            else None
+        )
+        ApplicationMetadata.set_detection_cache(self._detection_cache)
+        self._new_stacks_marker: Optional[str] = user_args.get("new_stacks_marker")
+        self._stack_ages = StackAges() if self._new_stacks_marker is not None else None
+        self._analyzers: List[Analyzer] = list(BUILTIN_HOTSPOTS)
+        self._sinks: List[SampleSink] = []
+        if user_args.get("alert_function_share"):
+            self._sinks.append(FunctionShareAlertSink(int(user_args["alert_function_share"]) / 100))
+        for webhook in user_args.get("webhooks") or []:
+            self._sinks.append(
+                WebhookSink(
+                    webhook,
+                    findings_only=bool(user_args.get("webhook_findings_only")),
+                    top_functions=int(user_args.get("webhook_top_functions") or 0),
+                    identity={"hostname": get_hostname(), "service_name": user_args.get("service_name")},
+                )
+            )
+        if self._analysis and user_args.get("analysis_plugins_dir"):
+            plugin_analyzers, plugin_sinks = load_plugins(user_args["analysis_plugins_dir"])
+            self._analyzers.extend(plugin_analyzers)
+            self._sinks.extend(plugin_sinks)
+        # the function shares of the previous window, for the deltas of the window summaries
+        self._previous_function_shares: Optional[Dict[str, float]] = None
+        if self._analysis and user_args.get("baseline"):
+            baseline_threshold = int(user_args.get("baseline_threshold") or DEFAULT_BASELINE_THRESHOLD)
+            self._analyzers.append(BaselineAnalyzer(load_baseline(user_args["baseline"]), baseline_threshold / 100))
         if self._collect_metadata:
             self._static_metadata = get_static_metadata(self._spawn_time, user_args, self._external_metadata_path)
         self._executor = concurrent.futures.ThreadPoolExecutor(max_workers=10)
@@ -161,6 +333,13 @@ class GProfiler:
         # the latter can be root only. the former can not. we should do this separation so we don't expose
         # files unnecessarily.
         container_names_client = ContainerNamesClient() if self._enrichment_options.container_names else None
+        # with --subprocesses, the processes to profile are the target processes and their descendants, refreshed
+        # at the start of every session.
+        self._process_tree_roots = (
+            list(processes_to_profile) if processes_to_profile is not None and user_args.get("subprocesses") else None
+        )
+        if self._process_tree_roots is not None:
+            processes_to_profile = get_process_tree(self._process_tree_roots)
         self._profiler_state = ProfilerState(
             stop_event=Event(),
             storage_dir=TEMPORARY_STORAGE_PATH,
@@ -170,9 +349,16 @@ class GProfiler:
             container_names_client=container_names_client,
             processes_to_profile=processes_to_profile,
             max_processes_per_profiler=int(user_args.get("max_processes_per_profiler", 0) or 0),
+            max_new_processes_per_session=int(user_args.get("max_new_processes_per_session", 0) or 0),
             max_system_processes_for_system_profilers=int(
                 user_args.get("max_system_processes_for_system_profilers", 0) or 0
             ),
+            process_filter=ProcessFilter.from_args(user_args),
+            error_records=(
+                ErrorRecordsWriter(user_args["error_records"], self._world_readable)
+                if user_args.get("error_records")
+                else None
+            ),
         )
         self.system_profiler, self.process_profilers = get_profilers(user_args, profiler_state=self._profiler_state)
         self._usage_logger = usage_logger
@@ -197,6 +383,10 @@ class GProfiler:
         if isinstance(self.system_profiler, NoopProfiler) and not self.process_profilers:
             raise NoProfilersEnabledError()
 
+    @property
+    def had_errors(self) -> bool:
+        return self._had_errors
+
     @property
     def all_profilers(self) -> Iterable[ProfilerInterface]:
         yield from self.process_profilers
@@ -214,6 +404,19 @@ class GProfiler:
     ) -> None:
         self.stop()
 
+    def _write_output_file(self, path: str, last_output_name: str, data: bytes) -> str:
+        """
+        Writes an output file (encrypted, if --encrypt-output is given) and points 'last_output_name' at it,
+        possibly deleting the previous one. Returns the path of the written file.
+        """
+        if self._encryption_recipients is not None:
+            data = age.encrypt(data, self._encryption_recipients)
+            path += age.AGE_FILE_SUFFIX
+            last_output_name += age.AGE_FILE_SUFFIX
+        write_file_restricted(path, data, self._world_readable)
+        self._update_last_output(last_output_name, path)
+        return path
+
     def _update_last_output(self, last_output_name: str, output_path: str) -> None:
         last_output = os.path.join(self._output_dir, last_output_name)
         prev_output = Path(last_output).resolve()
@@ -258,6 +461,7 @@ class GProfiler:
                 )
                 .replace(b"{{{START_TIME}}}", start_ts.encode())
                 .replace(b"{{{END_TIME}}}", end_ts.encode())
+                .replace(b"{{{COLOR_BY_CATEGORY}}}", b"true" if self._flamegraph_color_by_category else b"false")
             )
             return flamegraph_html.decode("utf-8")
         except Exception as e:
@@ -269,27 +473,196 @@ class GProfiler:
         collapsed_data: str,
         local_start_time: datetime.datetime,
         local_end_time: datetime.datetime,
+        findings: List[Finding],
+        frame_categories: Dict[str, int],
+        unknown_frames: Dict[str, int],
+        imports: ImportSamples,
     ) -> None:
         end_ts = get_iso8601_format_time(local_end_time)
         base_filename = os.path.join(self._output_dir, "profile_{}".format(escape_filename(end_ts)))
-        collapsed_path = base_filename + ".col"
-        Path(collapsed_path).write_text(collapsed_data, encoding="utf-8")
+        session_files = []
+        collapsed_path = self._write_output_file(
+            base_filename + ".col", "last_profile.col", collapsed_data.encode("utf-8")
+        )
+        session_files.append(collapsed_path)
         stripped_collapsed_data = self._strip_extra_data(collapsed_data)
-
-        # point last_profile.col at the new file; and possibly, delete the previous one.
-        self._update_last_output("last_profile.col", collapsed_path)
         logger.info(f"Saved collapsed stacks to {collapsed_path}")
 
         if self._flamegraph:
-            flamegraph_path = base_filename + ".html"
             flamegraph_html = self._generate_flamegraph_html(stripped_collapsed_data, local_start_time, local_end_time)
             if flamegraph_html:
-                Path(flamegraph_path).write_bytes(flamegraph_html.encode("utf-8"))
+                flamegraph_path = self._write_output_file(
+                    base_filename + ".html", "last_flamegraph.html", flamegraph_html.encode("utf-8")
+                )
+                session_files.append(flamegraph_path)
+                logger.info(f"Saved flamegraph to {flamegraph_path}")
 
-                # point last_flamegraph.html at the new file; and possibly, delete the previous one.
-                self._update_last_output("last_flamegraph.html", flamegraph_path)
+        if self._output_formats:
+            stacks = parse_one_collapsed(stripped_collapsed_data)
+            output_context = OutputContext(local_start_time, local_end_time, self._sampling_frequency)
+            for output_format in self._output_formats:
+                try:
+                    data = output_format.write(stacks, output_context)
+                except Exception:
+                    logger.exception(f"Failed to generate the {output_format.name} output")
+                    continue
+                output_path = self._write_output_file(
+                    base_filename + output_format.file_suffix, "last_profile" + output_format.file_suffix, data
+                )
+                session_files.append(output_path)
+                logger.info(f"Saved {output_format.name} output to {output_path}")
 
-                logger.info(f"Saved flamegraph to {flamegraph_path}")
+        if self._stack_ages is not None:
+            new_stacks_path = self._write_new_stacks(
+                self._stack_ages, base_filename, stripped_collapsed_data, local_end_time
+            )
+            if new_stacks_path is not None:
+                session_files.append(new_stacks_path)
+
+        if self._analysis:
+            summary = make_summary_report(
+                findings,
+                frame_categories,
+                unknown_frames,
+                imports,
+                get_iso8601_format_time(local_start_time),
+                end_ts,
+                self._schema_version,
+            )
+            summary_path = self._write_output_file(
+                base_filename + ".summary.json", "last_summary.json", json.dumps(summary, indent=2).encode("utf-8")
+            )
+            session_files.append(summary_path)
+            logger.info(f"Saved summary report to {summary_path}")
+
+        # written last, so its presence means all of the session's files were written completely.
+        manifest_path = base_filename + MANIFEST_SUFFIX
+        write_file_restricted(
+            manifest_path, json.dumps(make_manifest(session_files), indent=2).encode("utf-8"), self._world_readable
+        )
+        self._update_last_output("last_manifest.json", manifest_path)
+
+    def _write_new_stacks(
+        self, stack_ages: StackAges, base_filename: str, stripped_collapsed_data: str, end_time: datetime.datetime
+    ) -> Optional[str]:
+        """
+        Writes the stacks first seen since the --new-stacks-marker event, once it's marked.
+        """
+        assert self._new_stacks_marker is not None
+        stacks = parse_one_collapsed(stripped_collapsed_data)
+        stack_ages.update(stacks, end_time.replace(tzinfo=datetime.timezone.utc).timestamp())
+        marker_time = read_marker_time(self._new_stacks_marker)
+        if marker_time is None:
+            return None
+
+        new_stacks = stack_ages.stacks_since(stacks, marker_time)
+        header = {"new_stacks_since": get_iso8601_format_time(datetime.datetime.utcfromtimestamp(marker_time))}
+        lines = ["# " + json.dumps(header)] + [f"{stack} {count}" for stack, count in new_stacks.items()]
+        new_stacks_path = self._write_output_file(
+            base_filename + ".new.col", "last_profile.new.col", ("\n".join(lines) + "\n").encode("utf-8")
+        )
+        logger.info(f"Saved {len(new_stacks)} stacks new since the marker to {new_stacks_path}")
+        return new_stacks_path
+
+    def _analyze(self, process_profiles: ProcessToProfileData) -> List[Finding]:
+        """
+        Runs the analysis passes over the profiles collected by the runtime profilers, logging any findings.
+        """
+        if not self._analysis:
+            return []
+
+        findings = run_analyzers(self._analyzers, process_profiles, self._analysis_options)
+        for finding in findings:
+            logger.info(
+                f"Analysis finding: {finding.message}",
+                analyzer=finding.analyzer,
+                call_sites=[call_site.frame for call_site in finding.call_sites],
+                share_interval=[round(bound, 4) for bound in finding.share_interval],
+                low_confidence=finding.low_confidence,
+            )
+        return findings
+
+    def _filter_profiles(self, process_filter: ProcessFilter, profiles: ProcessToProfileData) -> ProcessToProfileData:
+        """
+        System-wide profilers (perf, PyPerf) sample all processes, so the process filter can't be applied before
+        they attach; instead, drop the stacks of the processes out of its scope.
+        """
+        filtered = {}
+        for pid, profile in profiles.items():
+            try:
+                process = Process(pid)
+            except NoSuchProcess:
+                # exited during the session - its user & path are unknown now.
+                if process_filter.matches_unknown():
+                    filtered[pid] = profile
+                continue
+            if process_filter.matches(process):
+                filtered[pid] = profile
+        return filtered
+
+    def _mark_warmup_profiles(self, process_profiles: ProcessToProfileData, session_start_time: float) -> None:
+        """
+        Profiles of processes which were still warming up (younger than --warmup) when the session started get
+        a [warmup] frame after the process name, separating their startup costs from the steady-state stacks.
+        """
+        for pid, profile in process_profiles.items():
+            if ProfilingErrorStack.is_error_stack(profile.stacks):
+                continue
+            try:
+                process_age = session_start_time - Process(pid).create_time()
+            except NoSuchProcess:
+                continue
+            if process_age < self._warmup:
+                profile.stacks = insert_frame_after_comm(profile.stacks, WARMUP_FRAME)
+
+    def _update_code_inventory(
+        self, code_inventory: CodeInventory, process_profiles: ProcessToProfileData, end_time: datetime.datetime
+    ) -> None:
+        try:
+            code_inventory.update(process_profiles, get_iso8601_format_time(end_time))
+            code_inventory.save()
+        except Exception:
+            logger.exception("Failed to update the code inventory")
+        else:
+            logger.debug("Updated the code inventory", functions=code_inventory.functions_count)
+
+    def _save_detection_cache(self, detection_cache: DetectionCache) -> None:
+        try:
+            detection_cache.save()
+        except Exception:
+            logger.exception("Failed to save the detection cache")
+
+    def _maybe_compact_caches(self) -> Optional[int]:
+        """
+        Periodically removes stale entries (of exited processes & containers) from the internal caches, so memory
+        stays flat in long runs. Returns the number of removed entries, or None if compaction is disabled.
+        """
+        if self._cache_compaction_interval == 0:
+            return None
+        if time.monotonic() - self._last_cache_compaction < self._cache_compaction_interval:
+            return 0
+
+        self._last_cache_compaction = time.monotonic()
+        application_metadata_entries = ApplicationMetadata.compact_cache()
+        container_names_entries = (
+            self._profiler_state.container_names_client.compact_cache()
+            if self._profiler_state.container_names_client is not None
+            else 0
+        )
+        logger.debug(
+            "Compacted internal caches",
+            application_metadata_entries=application_metadata_entries,
+            container_names_entries=container_names_entries,
+        )
+        return application_metadata_entries + container_names_entries
+
+    def _get_clock_sync_metadata(self, server: str) -> Dict[str, Any]:
+        try:
+            clock_offset_ms: Optional[float] = round(query_clock_offset(server) * 1000, 3)
+        except Exception as e:
+            logger.warning(f"Failed to measure the clock offset from {server}: {e}")
+            clock_offset_ms = None
+        return {"clock_sync_server": server, "clock_offset_ms": clock_offset_ms}
 
     def _strip_extra_data(self, collapsed_data: str) -> str:
         """
@@ -359,29 +732,90 @@ class GProfiler:
         self._hw_metrics_monitor.stop()
         for prof in self.all_profilers:
             prof.stop()
+        for sink in self._sinks:
+            try:
+                sink.close()
+            except Exception:
+                logger.exception("Failed to close a sample sink", sink=sink.name)
+        if self._shared_memory is not None:
+            self._shared_memory.close()
+
+    def _refresh_process_tree(self) -> None:
+        assert self._process_tree_roots is not None
+        processes_to_profile = self._profiler_state.processes_to_profile
+        assert processes_to_profile is not None
+        tree = get_process_tree(self._process_tree_roots)
+        started = [process.pid for process in tree if process not in processes_to_profile]
+        exited = [process.pid for process in processes_to_profile if process not in tree]
+        if started or exited:
+            logger.debug("Target process tree changed", started=started, exited=exited)
+        # updated in place, as the profilers share the list
+        processes_to_profile[:] = tree
 
     def _snapshot(self) -> None:
+        feature_flags.reload_file()
+        if self._process_tree_roots is not None:
+            self._refresh_process_tree()
+        session_start_time = time.time()
         local_start_time = datetime.datetime.utcnow()
         monotonic_start_time = time.monotonic()
         process_profilers_futures = []
         for prof in self.process_profilers:
             prof_future = self._executor.submit(prof.snapshot)
             prof_future.name = prof.name  # type: ignore # hack, add the profiler's name to the Future object
+            prof_future.profiler = prof  # type: ignore # hack, add the profiler to the Future object
             process_profilers_futures.append(prof_future)
         system_future = self._executor.submit(self.system_profiler.snapshot)
         system_future.name = "system"  # type: ignore # hack, add the profiler's name to the Future object
 
         process_profiles: ProcessToProfileData = {}
+        failed_profilers = []
         for future in concurrent.futures.as_completed(process_profilers_futures):
             # if either of these fail - log it, and continue.
             try:
-                process_profiles.update(future.result())
+                profiles = future.result()
             except Exception:
                 future_name = future.name  # type: ignore # hack, add the profiler's name to the Future object
                 logger.exception(f"{future_name} profiling failed")
+                failed_profilers.append(future_name)
+                self._had_errors = True
+                continue
+
+            # profilers may sample at different frequencies (e.g if limited by their MAX_FREQUENCY), so have
+            # the counts of all represent the same time.
+            sampling_frequency = future.profiler.sampling_frequency  # type: ignore # hack, see above
+            if sampling_frequency is not None and self._sampling_frequency is not None:
+                normalize_sample_weights(profiles, sampling_frequency, self._sampling_frequency)
+            # process-based profilers apply the process filter before attaching.
+            process_filter = self._profiler_state.process_filter
+            if process_filter is not None and not isinstance(future.profiler, ProcessProfilerBase):  # type: ignore
+                profiles = self._filter_profiles(process_filter, profiles)
+            process_profiles.update(profiles)
 
         local_end_time = local_start_time + datetime.timedelta(seconds=(time.monotonic() - monotonic_start_time))
 
+        # analyze before merging, while the stacks of each process are still as collected by its runtime profiler.
+        findings = self._analyze(process_profiles)
+        if self._sinks:
+            summary = make_window_summary(
+                process_profiles,
+                self._previous_function_shares,
+                local_start_time,
+                local_end_time,
+                failed_profilers,
+                findings,
+            )
+            self._previous_function_shares = summary.function_shares
+            run_sinks(self._sinks, summary)
+        frame_categories = aggregate_frame_categories(process_profiles) if self._analysis else {}
+        imports = aggregate_imports(process_profiles) if self._analysis else ImportSamples()
+        if self._code_inventory is not None:
+            self._update_code_inventory(self._code_inventory, process_profiles, local_end_time)
+        if self._detection_cache is not None:
+            self._save_detection_cache(self._detection_cache)
+        if self._warmup > 0:
+            self._mark_warmup_profiles(process_profiles, session_start_time)
+
         try:
             system_result = system_future.result()
         except Exception:
@@ -389,12 +823,35 @@ class GProfiler:
                 "Running perf failed; consider running gProfiler with '--perf-mode disabled' to avoid using perf",
             )
             raise
+        if self._profiler_state.process_filter is not None:
+            system_result = self._filter_profiles(self._profiler_state.process_filter, system_result)
+        unknown_frames = {reason: 0 for reason in UNKNOWN_FRAME_REASONS}
+        replace_unknown_frames(process_profiles, self._unknown_frame_placeholder, unknown_frames)
+        replace_unknown_frames(system_result, self._unknown_frame_placeholder, unknown_frames)
+        if self._extension_frames:
+            insert_extension_boundaries(process_profiles)
+            insert_extension_boundaries(system_result)
+        if self._collapse_recursion_depth > 0:
+            collapsed = collapse_recursion(process_profiles, self._collapse_recursion_depth) + collapse_recursion(
+                system_result, self._collapse_recursion_depth
+            )
+            if collapsed:
+                logger.debug(f"Collapsed the recursion cycles of {collapsed} deep stacks")
         metadata = (
             get_current_metadata(cast(ProfileMetadata, self._static_metadata))
             if self._collect_metadata
-            else {"hostname": get_hostname()}
+            else {"hostname": get_hostname(), "host_id": get_host_id_or_none(), "boot_id": get_boot_id_or_none()}
+        )
+        metadata.update(
+            {
+                "profiling_mode": self._profiler_state.profiling_mode,
+                "seed": self._seed,
+                "schema_version": self._schema_version,
+                "deprecations": self._deprecations,
+            }
         )
-        metadata.update({"profiling_mode": self._profiler_state.profiling_mode})
+        if self._clock_sync_server is not None:
+            metadata.update(self._get_clock_sync_metadata(self._clock_sync_server))
 
         # Add sampling event information if custom event is being used
         if isinstance(self.system_profiler, SystemProfiler) and self.system_profiler._custom_event_name:
@@ -441,6 +898,12 @@ class GProfiler:
                 }
             )
         metrics = self._system_metrics_monitor.get_metrics()
+        metrics.compacted_cache_entries = self._maybe_compact_caches()
+        if self._first_party_share_endpoints:
+            metrics.first_party_share = {
+                endpoint: first_party_share(process_profiles, endpoint)
+                for endpoint in self._first_party_share_endpoints
+            }
         hwmetrics = self._hw_metrics_monitor.get_hw_metrics()
         if hwmetrics is None:
             logger.info("No hw metrics were collected")
@@ -523,10 +986,20 @@ class GProfiler:
             )
 
         if self._output_dir:
-            self._generate_output_files(merged_result, local_start_time, local_end_time)
+            self._generate_output_files(
+                merged_result, local_start_time, local_end_time, findings, frame_categories, unknown_frames, imports
+            )
+
+        if self._shared_memory is not None:
+            if not self._shared_memory.publish(merged_result.encode("utf-8"), int(time.time() * 1000)):
+                logger.warning(
+                    "The profile doesn't fit in the shared memory section, consider increasing --shared-memory-size",
+                    size=len(merged_result),
+                    capacity=self._shared_memory.capacity,
+                )
 
         if self._profiler_api_client:
-            self._gpid = _submit_profile_logged(
+            gpid = _submit_profile_logged(
                 self._profiler_api_client,
                 local_start_time,
                 local_end_time,
@@ -536,6 +1009,9 @@ class GProfiler:
                 metrics,
                 self._gpid,
             )
+            if gpid is None:
+                self._had_errors = True
+            self._gpid = gpid or ""
 
         if time.monotonic() - self._last_diagnostics > DIAGNOSTICS_INTERVAL_S:
             self._last_diagnostics = time.monotonic()
@@ -565,6 +1041,7 @@ class GProfiler:
                     self._snapshot()
                 except Exception:
                     logger.exception("Profiling run failed!")
+                    self._had_errors = True
                 self._usage_logger.log_cycle()
 
                 # wait for one duration
@@ -586,7 +1063,10 @@ def _submit_profile_logged(
     spawn_time: float,
     metrics: "Metrics",
     gpid: str,
-) -> str:
+) -> Optional[str]:
+    """
+    Returns the gpid given by the server, or None if the upload failed.
+    """
     try:
         response_dict = client.submit_profile(
             start_time,
@@ -606,13 +1086,16 @@ def _submit_profile_logged(
     else:
         logger.info("Successfully uploaded profiling data to the server")
         return cast(str, response_dict.get("gpid", ""))
-    return ""
+    return None
 
 
 def send_collapsed_file_only(
     args: configargparse.Namespace,
     client: ProfilerAPIClient,
-) -> None:
+) -> bool:
+    """
+    Returns whether the file was uploaded.
+    """
     spawn_time = time.time()
     gpid = ""
     metrics = NoopSystemMetricsMonitor().get_metrics()
@@ -622,8 +1105,13 @@ def send_collapsed_file_only(
     metadata = (
         get_current_metadata(cast(ProfileMetadata, static_metadata))
         if args.collect_metadata
-        else {"hostname": get_hostname()}
+        else {"hostname": get_hostname(), "host_id": get_host_id_or_none(), "boot_id": get_boot_id_or_none()}
     )
+    manifest_path = find_session_manifest(args.file_path)
+    if manifest_path is not None:
+        # raises if the file was partially written or corrupted
+        verify_file(manifest_path, args.file_path)
+        logger.info(f"Verified {args.file_path} against its session manifest {manifest_path}")
     local_start_time, local_end_time, merged_result = concatenate_from_external_file(
         args.file_path,
         metadata,
@@ -634,16 +1122,80 @@ def send_collapsed_file_only(
             local_start_time is None and local_end_time is None
         ), "both start_time and end_time should be set, or none of them"
         local_start_time = local_end_time = datetime.datetime.utcnow()
-    _submit_profile_logged(
-        client,
-        local_start_time,
-        local_end_time,
-        merged_result,
-        args.profile_api_version,
-        spawn_time,
-        metrics,
-        gpid,
+    return (
+        _submit_profile_logged(
+            client,
+            local_start_time,
+            local_end_time,
+            merged_result,
+            args.profile_api_version,
+            spawn_time,
+            metrics,
+            gpid,
+        )
+        is not None
+    )
+
+
+def launch_profiled_command(command: List[str]) -> "subprocess.Popen[bytes]":
+    logger.info("Launching the profiled command", command=command)
+    command_process = subprocess.Popen(command)
+    # reap it as soon as it exits, so the profiling loop sees it's gone (rather than a zombie) and stops.
+    threading.Thread(target=command_process.wait, daemon=True).start()
+    return command_process
+
+
+def command_exit_code(returncode: int) -> int:
+    """
+    The exit code passed through for the profiled command. Like the shells, a command killed by a signal exits with
+    128 + the signal number (Popen reports it as the negated signal number, which isn't a valid exit code).
+    """
+    return 128 - returncode if returncode < 0 else returncode
+
+
+def write_command_result(
+    output_dir: str,
+    command: List[str],
+    exit_code: int,
+    start_time: datetime.datetime,
+    end_time: datetime.datetime,
+    world_readable: bool = False,
+) -> None:
+    result_path = os.path.join(output_dir, "command.json")
+    result = {
+        "command": command,
+        "exit_code": exit_code,
+        "start_time": get_iso8601_format_time(start_time),
+        "end_time": get_iso8601_format_time(end_time),
+    }
+    write_file_restricted(result_path, json.dumps(result, indent=2).encode("utf-8"), world_readable)
+    # it's written after the last session, so it has a manifest of its own (command.manifest.json)
+    write_file_restricted(
+        os.path.join(output_dir, "command" + MANIFEST_SUFFIX),
+        json.dumps(make_manifest([result_path]), indent=2).encode("utf-8"),
+        world_readable,
     )
+    logger.info(f"Profiled command exited with code {exit_code}, results are in {output_dir}")
+
+
+def run_downsample(args: configargparse.Namespace) -> None:
+    os.makedirs(args.output_dir, exist_ok=True)
+    try:
+        written, merged = downsample_directory(
+            args.downsample_input_dir,
+            args.output_dir,
+            args.downsample_window,
+            datetime.datetime.utcnow(),
+            args.world_readable,
+        )
+    except (OSError, ValueError, DownsampleError) as e:
+        print(f"Failed to downsample {args.downsample_input_dir}: {e}", file=sys.stderr)
+        sys.exit(EXIT_ERROR)
+
+    if args.downsample_delete_inputs:
+        for path in merged:
+            os.unlink(path)
+    print(f"Merged {len(merged)} files into {len(written)} windows of {args.downsample_window} seconds")
 
 
 def copy_resources(path: Path) -> None:
@@ -651,11 +1203,29 @@ def copy_resources(path: Path) -> None:
     shutil.copytree(resource_path(), path, dirs_exist_ok=True)
 
 
+def apply_safe_mode(parser: configargparse.ArgumentParser, args: configargparse.Namespace) -> None:
+    """
+    --safe-mode keeps the interaction with the profiled processes minimal: no native unwinding (PyPerf, perf's
+    DWARF mode - which copies the user stacks of the samples) and no enrichment (which reads the processes' files &
+    runs their executables).
+    """
+    if args.python_mode == "pyperf":
+        parser.error("--safe-mode can't be used with --python-mode pyperf (PyPerf unwinds native stacks)")
+    if args.perf_mode in ("dwarf", "smart"):
+        parser.error(f"--safe-mode can't be used with --perf-mode {args.perf_mode} (DWARF unwinding)")
+    if args.python_mode == "auto":
+        args.python_mode = "pyspy"
+    args.python_add_versions = False
+    args.application_metadata = False
+    args.collect_appids = False
+    args.container_names = False
+
+
 def parse_cmd_args() -> configargparse.Namespace:
-    parser = configargparse.ArgumentParser(
+    parser = ArgumentParser(
         description="This is the gProfiler CLI documentation. You can access the general"
         " documentation at https://github.com/intel/gprofiler#readme.",
-        auto_env_var_prefix="gprofiler_",
+        auto_env_var_prefix=ENV_VAR_PREFIX,
         add_config_file_help=True,
         add_env_var_help=False,
         default_config_files=["/etc/gprofiler/config.ini"],
@@ -706,6 +1276,13 @@ def parse_cmd_args() -> configargparse.Namespace:
         help="Do not generate local flamegraphs when -o is given (only collapsed stacks files)",
     )
     parser.set_defaults(flamegraph=True)
+    parser.add_argument(
+        "--flamegraph-color-by-category",
+        action="store_true",
+        default=False,
+        help="Color the Python frames of the local flamegraphs by the kind of code they're in: application code,"
+        " third-party packages (site-packages) or the standard library",
+    )
 
     parser.add_argument(
         "--mode",
@@ -724,6 +1301,68 @@ def parse_cmd_args() -> configargparse.Namespace:
         f" default: {DEFAULT_ALLOC_INTERVAL}",
     )
 
+    parser.add_argument(
+        "--output-format",
+        "--format",
+        dest="output_formats",
+        action="append",
+        default=None,
+        choices=sorted(get_output_formats_registry()),
+        help="Also write the profile of each session in this format, next to the collapsed file (with --output-dir)."
+        " 'cpuprofile' can be opened in VS Code and the Chrome/Edge DevTools, 'csv-functions' is a table of the self &"
+        " total samples of each function, to be opened in a spreadsheet, 'speedscope' can be opened in"
+        " https://www.speedscope.app. Can be given multiple times",
+    )
+    parser.add_argument(
+        "--format-opt",
+        dest="format_opts",
+        action="append",
+        default=None,
+        help="An option of the output formats, as key=value - given to all of the selected formats accepting it, or"
+        " as format.key=value to a single one, e.g 'speedscope.name=checkout'. Can be given multiple times",
+    )
+    parser.add_argument(
+        "--feature-flags-file",
+        dest="feature_flags_file",
+        metavar="PATH",
+        default=None,
+        help="A JSON file of kill switches that disable risky subsystems at runtime ("
+        + ", ".join(FEATURES)
+        + "), optionally for matching processes only. The file is re-read when it changes, see the README",
+    )
+    parser.add_argument(
+        "--encrypt-output",
+        dest="encrypt_output",
+        metavar="RECIPIENTS_FILE",
+        default=None,
+        help="Encrypt the output files to the age (https://age-encryption.org) X25519 recipients listed in this file"
+        " (one 'age1...' public key per line), so profiles stored on disk are encrypted at rest. Encrypted files get"
+        " a '.age' suffix, and can be decrypted with 'age --decrypt -i <key file>'",
+    )
+    parser.add_argument(
+        "--world-readable",
+        action="store_true",
+        default=False,
+        help="Create the output files (and the output directory, if gProfiler creates it) with the default permissions"
+        ". By default, they are accessible only to the user running gProfiler and the administrators, so profiling"
+        " data isn't exposed to other users on multi-user machines",
+    )
+    parser.add_argument(
+        "--shared-memory-output",
+        dest="shared_memory_output",
+        metavar="NAME",
+        default=None,
+        help="Publish the profile of the last session into the named shared-memory section (e.g 'Local\\gprofiler'"
+        " on Windows, /dev/shm/<NAME> on Linux), so sidecar readers can consume it without file I/O. See the README"
+        " for its layout",
+    )
+    parser.add_argument(
+        "--shared-memory-size",
+        dest="shared_memory_size",
+        default=DEFAULT_SHARED_MEMORY_SIZE,
+        help="The size of the --shared-memory-output section (human friendly sizes supported, e.g '64MiB')."
+        " Profiles which don't fit are not published (default: %(default)s)",
+    )
     parser.add_argument(
         "--rotating-output", action="store_true", default=False, help="Keep only the last profile result"
     )
@@ -736,6 +1375,113 @@ def parse_cmd_args() -> configargparse.Namespace:
         help="Comma separated list of processes that will be filtered to profile,"
         " given multiple times will append pids to one list",
     )
+    parser.add_argument(
+        "--subprocesses",
+        action="store_true",
+        dest="subprocesses",
+        default=False,
+        help="Profile the descendants of the target processes (given by --pids, or the command of profile-command)"
+        " as well, e.g gunicorn or celery workers. New children are picked up at the start of every session",
+    )
+    parser.add_argument(
+        "--include-user",
+        dest="include_users",
+        action="append",
+        default=None,
+        help="Profile only processes running as this user (on Windows, either 'DOMAIN\\user' or the bare user name)."
+        " Given multiple times, processes of any of the users are profiled",
+    )
+    parser.add_argument(
+        "--exclude-user",
+        dest="exclude_users",
+        action="append",
+        default=None,
+        help="Don't profile processes running as this user, e.g '--exclude-user SYSTEM'. Can be given multiple times",
+    )
+    parser.add_argument(
+        "--include-path",
+        dest="include_paths",
+        action="append",
+        default=None,
+        help="Profile only processes whose executable path matches this glob pattern, e.g '--include-path"
+        " \"D:\\apps\\**\"' ('*' matches across directories). Given multiple times, processes matching any of"
+        " the patterns are profiled",
+    )
+    parser.add_argument(
+        "--exclude-path",
+        dest="exclude_paths",
+        action="append",
+        default=None,
+        help="Don't profile processes whose executable path matches this glob pattern. Can be given multiple times",
+    )
+    parser.add_argument(
+        "--include-session",
+        dest="include_sessions",
+        action="extend",
+        default=None,
+        type=integers_list,
+        help="Comma separated list of session IDs (the Windows logon session, or the Unix session ID) whose processes"
+        " will be profiled, given multiple times will append session IDs to one list",
+    )
+    parser.add_argument(
+        "--exclude-session",
+        dest="exclude_sessions",
+        action="extend",
+        default=None,
+        type=integers_list,
+        help="Comma separated list of session IDs whose processes won't be profiled, e.g '--exclude-session 0' to"
+        " skip the Windows services session",
+    )
+    parser.add_argument(
+        "--include-uid",
+        dest="include_uids",
+        action="extend",
+        default=None,
+        type=strings_list,
+        help="Comma separated list of UIDs (on Windows, user SIDs such as 'S-1-5-18') whose processes will be"
+        " profiled, given multiple times will append them to one list",
+    )
+    parser.add_argument(
+        "--exclude-uid",
+        dest="exclude_uids",
+        action="extend",
+        default=None,
+        type=strings_list,
+        help="Comma separated list of UIDs (on Windows, user SIDs) whose processes won't be profiled",
+    )
+    parser.add_argument(
+        "--include-cmdline",
+        dest="include_cmdlines",
+        action="append",
+        default=None,
+        help="Profile only processes whose command line (its arguments joined by spaces) matches this regular"
+        " expression, e.g '--include-cmdline \"gunicorn .*myservice\"'. Given multiple times, processes matching"
+        " any of the expressions are profiled",
+    )
+    parser.add_argument(
+        "--exclude-cmdline",
+        dest="exclude_cmdlines",
+        action="append",
+        default=None,
+        help="Don't profile processes whose command line matches this regular expression, e.g '--exclude-cmdline"
+        " \"pip|setup\\.py\"'. Can be given multiple times",
+    )
+    parser.add_argument(
+        "--include-container",
+        dest="include_containers",
+        action="append",
+        default=None,
+        help="Profile only processes running in a container whose name matches this glob pattern (Linux only)."
+        " Given multiple times, processes of containers matching any of the patterns are profiled",
+    )
+    parser.add_argument(
+        "--exclude-container",
+        dest="exclude_containers",
+        action="append",
+        default=None,
+        help="Don't profile processes running in a container whose name matches this glob pattern (Linux only)."
+        " Can be given multiple times",
+    )
     parser.add_argument(
         "--max-processes-runtime-profiler",
         dest="max_processes_per_profiler",
@@ -745,6 +1491,15 @@ def parse_cmd_args() -> configargparse.Namespace:
         "When exceeded, profiles only the top N processes by CPU usage. "
         "Does not affect system-wide profilers (perf, eBPF). Default: %(default)s",
     )
+    parser.add_argument(
+        "--max-new-processes-per-session",
+        dest="max_new_processes_per_session",
+        type=nonnegative_integer,
+        default=0,
+        help="Maximum number of processes each runtime profiler starts profiling (attaches to) in a session"
+        " (0=unlimited). The processes beyond it are profiled in the next sessions, those waiting longest first,"
+        " so a burst of new processes doesn't delay the profiling of those already profiled. Default: %(default)s",
+    )
     parser.add_argument(
         "--skip-system-profilers-above",
         dest="max_system_processes_for_system_profilers",
@@ -875,8 +1630,7 @@ def parse_cmd_args() -> configargparse.Namespace:
         )
         connectivity.add_argument(
             "--glogger-server",
-            default=DEFAULT_API_SERVER_ADDRESS,
-            dest="api_server",
+            dest="glogger_server",
             help="Deprecated alias for --api-server.",
         )
         connectivity.add_argument(
@@ -894,6 +1648,42 @@ def parse_cmd_args() -> configargparse.Namespace:
             "--no-verify", help="Do not verify server certificates", action="store_false", dest="verify"
         )
 
+    profile_command = subparsers.add_parser(
+        PROFILE_COMMAND_SUBCOMMAND,
+        help="Launch a command and profile it until it exits, writing the profile, flamegraph, summary report and the"
+        " command's exit code into a new timestamped directory (under --output-dir, or the current directory)",
+    )
+    profile_command.add_argument(
+        "command",
+        nargs=configargparse.REMAINDER,
+        help="The command to profile, e.g 'gprofiler profile-command -- python -m pytest tests/'",
+    )
+
+    downsample = subparsers.add_parser(
+        DOWNSAMPLE_SUBCOMMAND,
+        help="Merge the collapsed files of many sessions into coarser windows in --output-dir, for long-term retention",
+    )
+    downsample.add_argument(
+        "--input-dir",
+        dest="downsample_input_dir",
+        required=True,
+        help="The directory of the collapsed files to downsample (e.g the --output-dir of a continuous gProfiler)",
+    )
+    downsample.add_argument(
+        "--window",
+        dest="downsample_window",
+        default=DEFAULT_DOWNSAMPLE_WINDOW,
+        help="The length of the output windows (human friendly durations supported, e.g '1h')."
+        " Windows are aligned to multiples of their length (default: %(default)s)",
+    )
+    downsample.add_argument(
+        "--delete-inputs",
+        dest="downsample_delete_inputs",
+        action="store_true",
+        default=False,
+        help="Delete the input files once they're merged into the output windows",
+    )
+
     extract_resources = subparsers.add_parser("extract-resources")
     extract_resources.set_defaults(func=copy_resources)
     extract_resources.add_argument(
@@ -904,10 +1694,32 @@ def parse_cmd_args() -> configargparse.Namespace:
     )
 
     parser.add_argument("--version", action="version", version=__version__)
+    parser.add_argument(
+        "--capabilities",
+        action=CapabilitiesAction,
+        help="Print a JSON manifest of the capabilities of this gProfiler (profilers, modes, output formats...)"
+        " and exit",
+    )
     parser.add_argument("-v", "--verbose", action="store_true", default=False, dest="verbose")
 
     logging_options = parser.add_argument_group("logging")
     logging_options.add_argument("--log-file", action="store", type=str, dest="log_file", default=DEFAULT_LOG_FILE)
+    logging_options.add_argument(
+        "--console-encoding",
+        type=str,
+        dest="console_encoding",
+        help="Encoding of the console output (e.g utf-8), defaults to the encoding of the console. Characters which"
+        " can't be represented in it are escaped",
+    )
+    logging_options.add_argument(
+        "--log-filter",
+        dest="log_filter",
+        default=None,
+        help="Per-module levels of the console logs, e.g 'profilers.python=debug,client=warning' - a comma-separated"
+        " list of <module>=<level>, with an optional bare <level> for all other modules (which otherwise log at"
+        " info, or debug with --verbose). Modules are gProfiler's logger names, and include their submodules."
+        " The log file always gets all logs",
+    )
     logging_options.add_argument(
         "--log-rotate-max-size",
         action="store",
@@ -929,6 +1741,15 @@ def parse_cmd_args() -> configargparse.Namespace:
         default=(os.getenv("GPROFILER_DONT_SEND_LOGS", None) is None),
         help="Disable sending logs to server",
     )
+    logging_options.add_argument(
+        "--error-records",
+        type=str,
+        dest="error_records",
+        default=None,
+        help="Append a JSON record (a line) of every per-process profiling failure to this file, classified by kind"
+        " (access-denied, version-unsupported, process-exited, read-fault or other), so failures can be aggregated"
+        " without parsing the logs",
+    )
 
     parser.add_argument(
         "--disable-container-names",
@@ -938,11 +1759,106 @@ def parse_cmd_args() -> configargparse.Namespace:
         help="gProfiler won't gather the container names of processes that run in containers",
     )
 
+    analysis_options = parser.add_argument_group("analysis")
+    analysis_options.add_argument(
+        "--disable-analysis",
+        action="store_false",
+        default=True,
+        dest="analysis",
+        help="Disable the analysis passes run over the collected profiles (and the summary report written"
+        " with --output-dir)",
+    )
+    analysis_options.add_argument(
+        "--analysis-hotspot-threshold",
+        type=integer_range(1, 101),
+        dest="analysis_hotspot_threshold",
+        default=DEFAULT_ANALYSIS_HOTSPOT_THRESHOLD,
+        help="Minimal percentage of the samples of a process spent in a flagged module (e.g logging, re) to report it"
+        " as a hotspot (default: %(default)s)",
+    )
+    analysis_options.add_argument(
+        "--code-inventory",
+        type=str,
+        dest="code_inventory",
+        help="Path of a JSON file to keep an inventory of the Python functions (file & function name) ever observed"
+        " in the samples, with the time they were first & last seen. The file is updated after every session and"
+        " kept across runs - functions missing from it over a long window are candidates for dead code",
+    )
+    analysis_options.add_argument(
+        "--analysis-plugins-dir",
+        type=str,
+        dest="analysis_plugins_dir",
+        help="Directory of analysis plugins (Python modules exposing a get_analyzers() function, returning"
+        " gprofiler.analysis.analyzer.Analyzer instances) to run in addition to the built-in analyzers",
+    )
+    analysis_options.add_argument(
+        "--alert-function-share",
+        type=integer_range(1, 101),
+        dest="alert_function_share",
+        help="Log an alert at the end of every session in which a single function is the innermost frame of at least"
+        " this percentage of the samples",
+    )
+    analysis_options.add_argument(
+        "--webhook",
+        action="append",
+        dest="webhooks",
+        help="URL to POST a JSON summary of every session to (its errors, the analysis findings and optionally the"
+        " top functions), e.g for ChatOps notifications. Can be given multiple times",
+    )
+    analysis_options.add_argument(
+        "--webhook-findings-only",
+        action="store_true",
+        dest="webhook_findings_only",
+        help="POST to --webhook only the summaries of sessions in which the analyzers produced findings",
+    )
+    analysis_options.add_argument(
+        "--webhook-top-functions",
+        type=nonnegative_integer,
+        dest="webhook_top_functions",
+        default=0,
+        help=f"Include the functions with the largest shares of the samples (up to {MAX_SUMMARY_FUNCTIONS}) in the"
+        " --webhook summaries (default: %(default)s)",
+    )
+    analysis_options.add_argument(
+        "--baseline",
+        type=str,
+        dest="baseline",
+        help="Path of a baseline collapsed file (e.g the .col file of a known-good session) to compare each session"
+        " with: functions whose share of the samples of a process grew over their share in the processes of the"
+        " same name in the baseline are reported as regressions",
+    )
+    analysis_options.add_argument(
+        "--baseline-threshold",
+        type=integer_range(1, 101),
+        dest="baseline_threshold",
+        default=DEFAULT_BASELINE_THRESHOLD,
+        help="Minimal increase, in percentage points of the samples of a process, of the share of a function over"
+        " its --baseline share to report it (default: %(default)s)",
+    )
+
+    analysis_options.add_argument(
+        "--new-stacks-marker",
+        type=str,
+        dest="new_stacks_marker",
+        help="Path of a marker file for an event, e.g a deploy: once it's created (or touched), every session also"
+        " writes the stacks first seen since its modification time into profile_<timestamp>.new.col."
+        " Requires --output-dir",
+    )
+
     continuous_command_parser = parser.add_argument_group("continuous")
     continuous_command_parser.add_argument(
         "--continuous", "-c", action="store_true", dest="continuous", help="Run in continuous mode"
     )
 
+    parser.add_argument(
+        "--dry-run",
+        action="store_true",
+        dest="dry_run",
+        default=False,
+        help="Validate the configuration, check the privileges and the server connection, and print the settings,"
+        " profilers and processes that a real run would use - without profiling anything",
+    )
+
     parser.add_argument(
         "--profile-api-version",
         action="store",
@@ -976,6 +1892,107 @@ def parse_cmd_args() -> configargparse.Namespace:
         help="Disable sending system and cloud metadata to the Performance Studio",
     )
 
+    parser.add_argument(
+        "--schema-version",
+        type=int,
+        dest="schema_version",
+        choices=SUPPORTED_OUTPUT_SCHEMA_VERSIONS,
+        default=OUTPUT_SCHEMA_VERSION,
+        help="Pin the schema version of the machine-readable outputs (collapsed file metadata header, summary report),"
+        " so a backend expecting an older version keeps working after gProfiler is upgraded (default: %(default)s)",
+    )
+
+    parser.add_argument(
+        "--warmup",
+        type=nonnegative_integer,
+        dest="warmup",
+        default=0,
+        help="Warmup period of processes, in seconds. Stacks of processes younger than that when a session starts get"
+        " a [warmup] frame after the process name, so startup costs are separated from the steady state."
+        " 0 disables it (default: %(default)s)",
+    )
+
+    parser.add_argument(
+        "--unknown-frame-placeholder",
+        dest="unknown_frame_placeholder",
+        default=DEFAULT_UNKNOWN_FRAME_PLACEHOLDER,
+        help="The frame to emit in place of frames the profilers couldn't resolve, '{reason}' is replaced by the"
+        " reason code (e.g 'no-mapping'). Pass '[unknown]' for the placeholder of older versions"
+        " (default: %(default)s)",
+    )
+
+    parser.add_argument(
+        "--no-extension-frames",
+        dest="extension_frames",
+        action="store_false",
+        default=True,
+        help="Don't insert an [ext:<module>] frame where Python code calls into a native extension module",
+    )
+
+    parser.add_argument(
+        "--collapse-recursion-depth",
+        type=nonnegative_integer,
+        dest="collapse_recursion_depth",
+        default=DEFAULT_COLLAPSE_RECURSION_DEPTH,
+        help="Stacks deeper than that many frames have their repetition cycles (deep recursion) collapsed into"
+        " synthetic 'frame xN' frames, to keep the output sizes and flamegraphs manageable. 0 disables it"
+        " (default: %(default)s)",
+    )
+
+    parser.add_argument(
+        "--seed",
+        type=nonnegative_integer,
+        dest="seed",
+        help="Seed for the randomized decisions taken on the samples (e.g rounding when scaling sample counts), so"
+        " runs can be reproduced exactly. If not given, a random seed is used. Either way, the seed is recorded in the"
+        " profile metadata",
+    )
+
+    parser.add_argument(
+        "--first-party-share-endpoint",
+        dest="first_party_share_endpoints",
+        action="append",
+        default=None,
+        metavar="FUNCTION_PATTERN",
+        help="Report the share of samples in first-party (application) code under the Python functions matching this"
+        " glob pattern (e.g '*.handle_checkout') in the metrics of each session, as 'first_party_share', so"
+        " performance SLOs can be defined on it. Can be given multiple times",
+    )
+    parser.add_argument(
+        "--cache-compaction-interval",
+        type=nonnegative_integer,
+        dest="cache_compaction_interval",
+        default=DEFAULT_CACHE_COMPACTION_INTERVAL,
+        help="Interval in seconds for removing stale entries (of exited processes & containers) from gProfiler's"
+        " internal caches, keeping memory usage flat in long runs. 0 disables compaction (default: %(default)s)",
+    )
+    parser.add_argument(
+        "--detection-cache",
+        type=str,
+        dest="detection_cache",
+        help="Path of a file to keep the per-executable detection results (e.g interpreter versions) in, keyed by"
+        " the hash of the executable, so restarts of gProfiler skip detecting them again",
+    )
+
+    parser.add_argument(
+        "--clock-sync-server",
+        type=str,
+        dest="clock_sync_server",
+        help="NTP server (host[:port]) to measure the offset of the local clock from, on each profiling session."
+        " The offset is reported in the profile metadata (clock_offset_ms), for correlation with profiles & traces"
+        " collected on other hosts",
+    )
+
+    parser.add_argument(
+        "--safe-mode",
+        action="store_true",
+        dest="safe_mode",
+        default=False,
+        help="Keep the interaction with the profiled processes minimal, for fragile or security-sensitive targets:"
+        " disables native unwinding (PyPerf, and perf's DWARF mode) and the enrichment of profiles (application"
+        " metadata & identifiers, container names, Python package versions)",
+    )
+
     parser.add_argument(
         "--disable-application-identifiers",
         action="store_false",
@@ -1090,7 +2107,13 @@ def parse_cmd_args() -> configargparse.Namespace:
             help="The default perfspect tool collection time is 60 second.",
         )
 
-    args = parser.parse_args()
+    args = parse_config_args(parser)
+
+    if getattr(args, "glogger_server", None) is not None:
+        args.api_server = args.glogger_server
+
+    if args.safe_mode:
+        apply_safe_mode(parser, args)
 
     args.perf_inject = args.nodejs_mode == "perf"
     args.perf_node_attach = args.nodejs_mode == "attach-maps"
@@ -1103,6 +2126,20 @@ def parse_cmd_args() -> configargparse.Namespace:
             "Use --perf-event-period for period-based sampling or -f for frequency-based sampling."
         )
 
+    if args.subcommand == PROFILE_COMMAND_SUBCOMMAND:
+        if args.command and args.command[0] == "--":
+            args.command = args.command[1:]
+        if not args.command:
+            parser.error(f"Must provide a command to profile in {PROFILE_COMMAND_SUBCOMMAND} mode")
+        if args.pids_to_profile is not None:
+            parser.error(f"--pids is not supported in {PROFILE_COMMAND_SUBCOMMAND} mode, the command is profiled")
+        if args.profiling_mode == CPU_PROFILING_MODE and args.frequency is None and not args.perf_event_period:
+            args.frequency = PROFILE_COMMAND_FREQUENCY
+        # profile in sessions of --profiling-duration until the command exits
+        args.continuous = True
+        session_dir = "gprofiler_{}".format(escape_filename(get_iso8601_format_time(datetime.datetime.utcnow())))
+        args.output_dir = os.path.join(args.output_dir or os.getcwd(), session_dir)
+
     if args.profiling_mode == CPU_PROFILING_MODE:
         if args.alloc_interval:
             parser.error("--alloc-interval is only allowed in allocation profiling (--mode=allocation)")
@@ -1118,6 +2155,20 @@ def parse_cmd_args() -> configargparse.Namespace:
     if args.subcommand == UPLOAD_FILE_SUBCOMMAND:
         args.upload_results = True
 
+    if args.subcommand == DOWNSAMPLE_SUBCOMMAND:
+        if not args.output_dir:
+            parser.error(f"Must provide --output-dir in {DOWNSAMPLE_SUBCOMMAND} mode")
+        if not os.path.isdir(args.downsample_input_dir):
+            parser.error(f"--input-dir {args.downsample_input_dir!r} is not a directory")
+        if os.path.realpath(args.downsample_input_dir) == os.path.realpath(args.output_dir):
+            parser.error("--input-dir and --output-dir must be different directories")
+        try:
+            args.downsample_window = int(humanfriendly.parse_timespan(args.downsample_window))
+        except humanfriendly.InvalidTimespan:
+            parser.error(f"Invalid --window {args.downsample_window!r}")
+        if args.downsample_window <= 0:
+            parser.error("--window must be positive")
+
     if args.subcommand == "extract-resources":
         args.extract_resources = True
     else:
@@ -1147,6 +2198,9 @@ def parse_cmd_args() -> configargparse.Namespace:
     if args.profile_spawned_processes and args.pids_to_profile is not None:
         parser.error("--pids is not allowed when profiling spawned processes")
 
+    if args.subprocesses and args.pids_to_profile is None and args.subcommand != PROFILE_COMMAND_SUBCOMMAND:
+        parser.error(f"--subprocesses requires --pids or the {PROFILE_COMMAND_SUBCOMMAND} subcommand")
+
     if args.enable_heartbeat_server:
         if not args.upload_results:
             parser.error("--enable-heartbeat-server requires --upload-results to be enabled")
@@ -1155,6 +2209,85 @@ def parse_cmd_args() -> configargparse.Namespace:
         if not args.service_name:
             parser.error("--enable-heartbeat-server requires --service-name to be provided")
 
+    for cmdline_pattern in (args.include_cmdlines or []) + (args.exclude_cmdlines or []):
+        try:
+            re.compile(cmdline_pattern)
+        except re.error as e:
+            parser.error(f"Invalid command line regular expression {cmdline_pattern!r}: {e}")
+
+    if not is_windows():
+        for uid in (args.include_uids or []) + (args.exclude_uids or []):
+            if not uid.isdigit():
+                parser.error(f"Invalid UID {uid!r}, UIDs are numeric (SIDs are supported on Windows only)")
+
+    if is_windows() and (args.include_containers or args.exclude_containers):
+        parser.error("--include-container / --exclude-container are supported on Linux only")
+
+    if args.format_opts:
+        if not args.output_formats:
+            parser.error("--format-opt requires --output-format")
+        try:
+            parse_format_options(args.format_opts, args.output_formats)
+        except OutputFormatOptionError as e:
+            parser.error(f"Invalid --format-opt: {e}")
+
+    if args.new_stacks_marker is not None and not args.output_dir:
+        parser.error("--new-stacks-marker requires --output-dir")
+
+    if args.dry_run and args.subcommand in (UPLOAD_FILE_SUBCOMMAND, DOWNSAMPLE_SUBCOMMAND, "extract-resources"):
+        parser.error(f"--dry-run is not supported in {args.subcommand} mode")
+
+    if args.console_encoding is not None:
+        try:
+            codecs.lookup(args.console_encoding)
+        except LookupError:
+            parser.error(f"Unknown --console-encoding {args.console_encoding!r}")
+
+    if args.log_filter is not None:
+        try:
+            parse_log_filter(args.log_filter)
+        except ValueError as e:
+            parser.error(f"Invalid --log-filter {args.log_filter!r}: {e}")
+
+    if args.analysis_plugins_dir is not None and not os.path.isdir(args.analysis_plugins_dir):
+        parser.error(f"--analysis-plugins-dir {args.analysis_plugins_dir!r} is not a directory")
+
+    for webhook in args.webhooks or []:
+        url = urlparse(webhook)
+        if url.scheme not in ("http", "https") or not url.netloc:
+            parser.error("--webhook must be an http:// or https:// URL")
+
+    if args.baseline is not None and not os.path.isfile(args.baseline):
+        parser.error(f"--baseline {args.baseline!r} is not a file")
+
+    try:
+        args.unknown_frame_placeholder.format(reason="")
+    except (KeyError, IndexError, ValueError):
+        parser.error(
+            f"Invalid --unknown-frame-placeholder {args.unknown_frame_placeholder!r}, only '{{reason}}' can be used"
+        )
+
+    if args.shared_memory_output is not None:
+        if not is_windows() and ("/" in args.shared_memory_output or args.shared_memory_output in (".", "..")):
+            parser.error(f"Invalid --shared-memory-output name {args.shared_memory_output!r}")
+        try:
+            if humanfriendly.parse_size(args.shared_memory_size, binary=True) <= SHARED_MEMORY_HEADER_SIZE:
+                parser.error(f"--shared-memory-size {args.shared_memory_size!r} is too small")
+        except humanfriendly.InvalidSize:
+            parser.error(f"Invalid --shared-memory-size {args.shared_memory_size!r}")
+
+    if args.feature_flags_file is not None:
+        try:
+            read_feature_flags_file(args.feature_flags_file)
+        except FeatureFlagsError as e:
+            parser.error(f"Invalid --feature-flags-file: {e}")
+
+    if args.encrypt_output is not None:
+        try:
+            age.read_recipients_file(args.encrypt_output)
+        except (OSError, age.InvalidRecipientError) as e:
+            parser.error(f"Invalid --encrypt-output recipients file: {e}")
+
     # Validate --perf-event-period only works with --perf-event
     if args.perf_event_period and not args.perf_event:
         parser.error("--perf-event-period requires --perf-event to be specified")
@@ -1221,13 +2354,13 @@ def _add_profilers_arguments(parser: configargparse.ArgumentParser) -> None:
 def verify_preconditions(args: configargparse.Namespace, processes_to_profile: Optional[List[Process]]) -> None:
     if not args.rootless and not is_root():
         print("Not running as root, rerun with --rootless or as root.", file=sys.stderr)
-        sys.exit(1)
+        sys.exit(EXIT_PERMISSION)
     elif args.rootless and is_root():
         print(
             "Conflict, running with --rootless and as root, rerun with --rootless or as root (but not both).",
             file=sys.stderr,
         )
-        sys.exit(1)
+        sys.exit(EXIT_CONFIG_ERROR)
 
     if args.pid_ns_check and not is_running_in_init_pid():
         print(
@@ -1236,31 +2369,33 @@ def verify_preconditions(args: configargparse.Namespace, processes_to_profile: O
             "You can disable this check with --disable-pidns-check.",
             file=sys.stderr,
         )
-        sys.exit(1)
+        sys.exit(EXIT_CONFIG_ERROR)
 
-    try:
-        if is_linux() and not grab_gprofiler_mutex():
-            # Another gProfiler instance is running (or lock is held).
-            # Treat as a precondition failure (exit with error status).
-            sys.exit(1)
-    except Exception:
-        traceback.print_exc()
-        print(
-            "Could not acquire gProfiler's lock due to an error. Are you running gProfiler in privileged mode?",
-            file=sys.stderr,
-        )
-        sys.exit(1)
+    # a dry run doesn't profile, so it can check a configuration while another gProfiler instance is running
+    if not args.dry_run:
+        try:
+            if is_linux() and not grab_gprofiler_mutex():
+                # Another gProfiler instance is running (or lock is held).
+                # Treat as a precondition failure (exit with error status).
+                sys.exit(EXIT_ERROR)
+        except Exception:
+            traceback.print_exc()
+            print(
+                "Could not acquire gProfiler's lock due to an error. Are you running gProfiler in privileged mode?",
+                file=sys.stderr,
+            )
+            sys.exit(EXIT_PERMISSION)
 
     if args.log_usage and get_run_mode() not in ("k8s", "container"):
         # TODO: we *can* move into another cpuacct cgroup, to let this work also when run as a standalone
         # executable.
         print("--log-usage is available only when run as a container!", file=sys.stderr)
-        sys.exit(1)
+        sys.exit(EXIT_CONFIG_ERROR)
 
     if processes_to_profile is not None:
         if len(processes_to_profile) == 0:
             print("There aren't any alive processes provided via --pid PID list")
-            sys.exit(1)
+            sys.exit(EXIT_UNSUPPORTED_TARGET)
 
 
 def log_system_info() -> None:
@@ -1321,17 +2456,6 @@ def pids_to_processes(args: configargparse.Namespace) -> Optional[List[Process]]
         return None
 
 
-def warn_about_deprecated_args(args: configargparse.Namespace) -> None:
-    if args.spark_sample_period != 120:
-        logger.warning("--spark-sample-period is deprecated and removed in version 1.42.0")
-
-    if args.collect_spark_metrics:
-        logger.warning("--collect-spark-metrics is deprecated and removed in version 1.42.0")
-
-    if args.databricks_job_name_as_service_name:
-        logger.warning("--databricks-job-name-as-service-name is deprecated and removed in version 1.49.0")
-
-
 def main() -> None:
     args = parse_cmd_args()
 
@@ -1340,6 +2464,10 @@ def main() -> None:
             args.func(args.resources_dest)
             return
 
+    if args.subcommand == DOWNSAMPLE_SUBCOMMAND:
+        run_downsample(args)
+        return
+
     processes_to_profile = pids_to_processes(args)
 
     if is_windows() or get_aws_execution_env() == "AWS_ECS_FARGATE":
@@ -1363,18 +2491,25 @@ def main() -> None:
         args.log_rotate_max_size,
         args.log_rotate_backup_count,
         remote_logs_handler,
+        args.console_encoding,
+        args.log_filter,
     )
 
-    warn_about_deprecated_args(args)
-    setup_env(args.disable_core_files, args.pid_file)
+    report_deprecated_options(find_deprecated_options(args.__dict__))
+    if not args.dry_run:
+        setup_env(args.disable_core_files, args.pid_file)
 
     # assume we run in the root cgroup (when containerized, that's our view)
     usage_logger = CgroupsUsageLogger(logger, "/") if args.log_usage else NoopUsageLogger()
 
+    command_process: Optional["subprocess.Popen[bytes]"] = None
+    exit_code = EXIT_SUCCESS
     try:
         logger.info(
             "Running gProfiler", version=__version__, commandline=" ".join(sys.argv[1:]), arguments=args.__dict__
         )
+        if args.rootless:
+            logger.warning("Running in rootless mode", restrictions=rootless_restrictions())
         if processes_to_profile is not None:
             logger.info("Target PIDs given by --pids", pids=[process.pid for process in processes_to_profile])
         if args.controller_pid is not None:
@@ -1382,7 +2517,7 @@ def main() -> None:
                 controller_process: Optional[Process] = Process(args.controller_pid)
             except NoSuchProcess:
                 logger.error("Give controller PID is not running!")
-                sys.exit(1)
+                sys.exit(EXIT_UNSUPPORTED_TARGET)
         else:
             controller_process = None
 
@@ -1390,12 +2525,12 @@ def main() -> None:
         if args.external_metadata is not None:
             if args.subcommand == UPLOAD_FILE_SUBCOMMAND:
                 logger.error(f"External metadata is not supported in {UPLOAD_FILE_SUBCOMMAND} mode!")
-                sys.exit(1)
+                sys.exit(EXIT_CONFIG_ERROR)
 
             external_metadata_path = Path(args.external_metadata)
             if not external_metadata_path.is_file():
                 logger.error(f"External metadata file {args.external_metadata} does not exist!")
-                sys.exit(1)
+                sys.exit(EXIT_CONFIG_ERROR)
 
         heartbeat_file_path: Optional[Path] = None
         if args.heartbeat_file is not None:
@@ -1406,24 +2541,29 @@ def main() -> None:
             perfspect_path = Path(args.tool_perfspect_path)
             if not perfspect_path.is_file():
                 logger.error(f"PerfSpect tool {args.tool_perfspect_path} does not exist!")
-                sys.exit(1)
+                sys.exit(EXIT_CONFIG_ERROR)
 
         try:
             log_system_info()
         except Exception:
             logger.exception("Encountered an exception while getting basic system info")
 
-        if args.output_dir:
+        if args.output_dir and not args.dry_run:
             try:
+                output_dir_exists = os.path.isdir(args.output_dir)
                 os.makedirs(args.output_dir, exist_ok=True)
+                # restrict only a directory we've created - an existing one may be shared with other programs.
+                if not output_dir_exists and not args.world_readable:
+                    restrict_to_owner(args.output_dir)
             except (FileExistsError, NotADirectoryError):
                 logger.error(
                     "Output directory / a component in its path already exists as a non-directory!"
                     f"Please check the path {args.output_dir!r}"
                 )
-                sys.exit(1)
+                sys.exit(EXIT_CONFIG_ERROR)
 
-        mkdir_owned_root_wrapper(TEMPORARY_STORAGE_PATH)
+        if not args.dry_run:
+            mkdir_owned_root_wrapper(TEMPORARY_STORAGE_PATH)
 
         try:
             client_kwargs = {}
@@ -1444,7 +2584,7 @@ def main() -> None:
             )
         except APIError as e:
             logger.error(f"Server error: {e}")
-            sys.exit(1)
+            sys.exit(EXIT_ERROR)
         except RequestException as e:
             proxy = get_https_proxy()
             proxy_str = repr(proxy) if proxy is not None else "none"
@@ -1453,12 +2593,17 @@ def main() -> None:
                 " or you might require a proxy to access it from your environment?"
                 f" Proxy used: {proxy_str}. Error: {e}"
             )
-            sys.exit(1)
+            sys.exit(EXIT_ERROR)
+
+        if args.dry_run:
+            print(json.dumps(make_dry_run_report(args, processes_to_profile), indent=2, default=str))
+            return
 
         if args.subcommand == UPLOAD_FILE_SUBCOMMAND:
             assert external_metadata_path is None  # not expecting it
             assert profiler_api_client is not None  # it's always initialized in upload-file mode
-            send_collapsed_file_only(args, profiler_api_client)
+            if not send_collapsed_file_only(args, profiler_api_client):
+                sys.exit(EXIT_ERROR)
             return
 
         enrichment_options = EnrichmentOptions(
@@ -1494,6 +2639,13 @@ def main() -> None:
             finally:
                 manager.stop()
         else:
+            if args.subcommand == PROFILE_COMMAND_SUBCOMMAND:
+                command_start_time = datetime.datetime.utcnow()
+                command_process = launch_profiled_command(args.command)
+                # profile the command, and stop once it exits
+                processes_to_profile = [Process(command_process.pid)]
+                controller_process = Process(command_process.pid)
+
             # Normal profiling mode
             gprofiler = GProfiler(
                 output_dir=args.output_dir,
@@ -1526,21 +2678,39 @@ def main() -> None:
                 gprofiler.run_continuous()
             else:
                 gprofiler.run_single()
+            if gprofiler.had_errors:
+                exit_code = EXIT_PARTIAL_SUCCESS
+
+            if command_process is not None:
+                write_command_result(
+                    args.output_dir,
+                    args.command,
+                    command_process.wait(),
+                    command_start_time,
+                    datetime.datetime.utcnow(),
+                    args.world_readable,
+                )
 
     except KeyboardInterrupt:
         pass
     except NoProfilersEnabledError:
         logger.error("All profilers are disabled! Please enable at least one of them!")
-        sys.exit(1)
+        sys.exit(EXIT_CONFIG_ERROR)
     except ExternalMetadataStaleError:
         logger.error("External metadata file is stale! Please update it or disable external metadata, and try again.")
-        sys.exit(1)
+        sys.exit(EXIT_ERROR)
     except Exception:
         logger.exception("Unexpected error occurred")
-        sys.exit(1)
+        sys.exit(EXIT_ERROR)
 
     usage_logger.log_run()
 
+    if command_process is not None and command_process.returncode != 0:
+        # so CI jobs running profiled commands keep failing when the command fails
+        sys.exit(command_exit_code(command_process.returncode))
+    if exit_code != EXIT_SUCCESS:
+        sys.exit(exit_code)
+
 
 if __name__ == "__main__":
     main()
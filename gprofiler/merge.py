@@ -20,6 +20,7 @@ from dataclasses import dataclass
 from datetime import datetime
 from typing import Any, Dict, List, Optional, Tuple
 
+from gprofiler.consts import OUTPUT_SCHEMA_VERSION, SUPPORTED_OUTPUT_SCHEMA_VERSIONS
 from gprofiler.containers_client import ContainerNamesClient
 from gprofiler.gprofiler_types import ProcessToProfileData, ProfileData, ProfilingErrorStack, StackToSampleCount
 from gprofiler.hw_metrics import HWMetrics
@@ -32,6 +33,13 @@ from gprofiler.utils import merge_dicts
 
 logger = get_logger_adapter(__name__)
 
+# used for all randomized decisions taken on the samples, so they can be reproduced (see --seed).
+_samples_random = random.Random()
+
+
+def seed_samples_random(seed: int) -> None:
+    _samples_random.seed(seed)
+
 
 def scale_sample_counts(stacks: StackToSampleCount, ratio: float) -> StackToSampleCount:
     if ratio == 1:
@@ -43,13 +51,32 @@ def scale_sample_counts(stacks: StackToSampleCount, ratio: float) -> StackToSamp
         # If we were to round all of the sample counts it could skew the results. By using a random factor,
         # we mostly solve this by randomly rounding up / down stacks.
         # The higher the fractional part of the new count, the more likely it is to be rounded up instead of down
-        scaled_value = math.ceil(new_count) if random.random() <= math.modf(new_count)[0] else math.floor(new_count)
+        scaled_value = (
+            math.ceil(new_count) if _samples_random.random() <= math.modf(new_count)[0] else math.floor(new_count)
+        )
         # TODO: For more accurate truncation, check if there's a common frame for the truncated stacks and combine them
         if scaled_value != 0:
             scaled_stacks[stack] = scaled_value
     return scaled_stacks
 
 
+def normalize_sample_weights(
+    process_profiles: ProcessToProfileData, sampling_frequency: int, reference_frequency: int
+) -> None:
+    """
+    Each sample represents 1 / frequency seconds, so a stack's weight (the time spent in it) is count * interval.
+    Profiles collected at a frequency other than the reference one are scaled so that all counts in the output
+    represent the same interval (1 / reference_frequency), and remain proportional to time.
+    """
+    if sampling_frequency == reference_frequency:
+        return
+
+    ratio = reference_frequency / sampling_frequency
+    for profile in process_profiles.values():
+        if not ProfilingErrorStack.is_error_stack(profile.stacks):
+            profile.stacks = scale_sample_counts(profile.stacks, ratio)
+
+
 def _make_profile_metadata(
     container_names_client: Optional[ContainerNamesClient],
     add_container_names: bool,
@@ -76,6 +103,7 @@ def _make_profile_metadata(
         "application_metadata": application_metadata,
         "application_metadata_enabled": application_metadata_enabled,
         "profiling_mode": metadata["profiling_mode"],
+        "schema_version": metadata.get("schema_version", OUTPUT_SCHEMA_VERSION),
         "hwmetrics": hwmetrics.metrics_data if hwmetrics is not None else None,
         "htmlblob": hwmetrics.metrics_html if hwmetrics is not None else None,
         "flamegraph_html": flamegraph_html,
@@ -200,6 +228,13 @@ def concatenate_from_external_file(
             if index == 0:
                 assert line.startswith("#")
                 read_metadata = json.loads(line[1:])
+                # files written before schema versions were introduced have the first version.
+                schema_version = read_metadata.get("schema_version", SUPPORTED_OUTPUT_SCHEMA_VERSIONS[0])
+                if schema_version not in SUPPORTED_OUTPUT_SCHEMA_VERSIONS:
+                    raise ValueError(
+                        f"Unsupported schema version {schema_version} of {collapsed_file_path}, supported versions"
+                        f" are {SUPPORTED_OUTPUT_SCHEMA_VERSIONS}"
+                    )
                 metadata = merge_dicts(read_metadata, obtained_metadata)
                 try:
                     start_time = datetime.fromisoformat(metadata["start_time"])
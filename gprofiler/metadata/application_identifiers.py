@@ -23,12 +23,14 @@ import re
 from typing import Dict, List, Optional, TextIO, Tuple
 
 from granulate_utils.linux.ns import resolve_host_path, resolve_proc_root_links
-from psutil import NoSuchProcess, Process
+from psutil import AccessDenied, NoSuchProcess, Process
 
+from gprofiler.feature_flags import APPLICATION_IDENTIFIERS, feature_flags
 from gprofiler.log import get_logger_adapter
 from gprofiler.metadata.base_application_identifier import _ApplicationIdentifier
 from gprofiler.metadata.enrichment import EnrichmentOptions
 from gprofiler.platform import is_linux
+from gprofiler.utils.process import process_image_id
 
 if is_linux():
     from gprofiler.metadata.application_identifiers_java import (
@@ -388,7 +390,6 @@ class ApplicationIdentifiers:
             cls.identifiers_map["java_spark"] = cls.identifiers_map["java"] + [_JavaSparkApplicationIdentifier()]
 
 
-@functools.lru_cache(4096)  # NOTE: arbitrary cache size
 def get_app_id(process: Process, runtime: str, aggregate_all: bool = False) -> Optional[str]:
     """
     Tries to identify the application running in a given process, application identification is fully heuristic,
@@ -398,7 +399,21 @@ def get_app_id(process: Process, runtime: str, aggregate_all: bool = False) -> O
     assert _ApplicationIdentifier.enrichment_options is not None, "not initialized?"
     if not _ApplicationIdentifier.enrichment_options.application_identifiers:
         return None
+    if feature_flags.is_disabled(APPLICATION_IDENTIFIERS, process):
+        return None
 
+    try:
+        image_id = process_image_id(process)
+    except NoSuchProcess:
+        return None
+    except (AccessDenied, OSError):
+        image_id = ""  # can't tell, cached by the process alone
+    # the image id is part of the cache key, so a process that exec()ed into another program is identified again.
+    return _get_app_id_cached(process, image_id, runtime, aggregate_all)
+
+
+@functools.lru_cache(4096)  # NOTE: arbitrary cache size
+def _get_app_id_cached(process: Process, image_id: str, runtime: str, aggregate_all: bool) -> Optional[str]:
     appids = []
     for identifier in ApplicationIdentifiers.identifiers_map[runtime]:
         try:
@@ -16,15 +16,18 @@
 
 import functools
 from threading import Event, Lock
-from typing import Any, Callable, Dict, Optional
+from typing import Any, Callable, Dict, Optional, Tuple
 
 from granulate_utils.linux.elf import elf_arch_to_uname_arch, get_elf_arch
 from granulate_utils.linux.process import is_process_running, process_exe, read_process_execfn
-from psutil import NoSuchProcess, Process, ZombieProcess
+from psutil import AccessDenied, NoSuchProcess, Process, ZombieProcess
 
+from gprofiler.feature_flags import APPLICATION_METADATA, feature_flags
 from gprofiler.log import get_logger_adapter
+from gprofiler.metadata.detection_cache import DetectionCache, process_exe_hash
 from gprofiler.metadata.versions import get_exe_version
 from gprofiler.platform import is_windows
+from gprofiler.utils.process import process_image_id
 
 logger = get_logger_adapter(__name__)
 
@@ -33,33 +36,92 @@ class ApplicationMetadata:
     # chosen arbitrarily to be large enough to contain all processes we could possibly profile in one session; while
     # not exploding memory too much.
     _CACHE_CLEAR_ON_SIZE = 0x4000
-    _cache: Dict[Process, Optional[Dict]] = {}
+    # process -> (image id of the process when its metadata was collected, metadata)
+    _cache: Dict[Process, Tuple[str, Optional[Dict]]] = {}
     _cache_clear_lock = Lock()
     _metadata_exception_logs_count = 0
     _MAX_METADATA_EXCEPTION_LOGS = 100
     _GET_VERSION_TIMEOUT = 3
+    # persists the detected versions across runs, if enabled
+    _detection_cache: Optional[DetectionCache] = None
 
     def __init__(self, stop_event: Event):
         self._stop_event = stop_event
 
-    def _clear_cache(self) -> None:
-        with self._cache_clear_lock:
-            for process in list(self._cache.keys()):
+    @classmethod
+    def compact_cache(cls) -> int:
+        """
+        Removes the entries of processes which are no longer running. Returns the number of removed entries.
+        """
+        removed = 0
+        with cls._cache_clear_lock:
+            for process in list(cls._cache.keys()):
                 if not is_process_running(process):
-                    del self._cache[process]
+                    del cls._cache[process]
+                    removed += 1
+        return removed
+
+    @classmethod
+    def set_detection_cache(cls, detection_cache: Optional[DetectionCache]) -> None:
+        cls._detection_cache = detection_cache
 
     def get_exe_version(self, process: Process, version_arg: str = "--version", try_stderr: bool = False) -> str:
         return get_exe_version(process, self._stop_event, self._GET_VERSION_TIMEOUT, version_arg, try_stderr)
 
-    @functools.lru_cache(4096)
     def get_exe_version_cached(self, process: Process, version_arg: str = "--version", try_stderr: bool = False) -> str:
-        return self.get_exe_version(process, version_arg, try_stderr)
+        # keyed by the image id as well, so the version is checked again if the process exec()s into another program.
+        try:
+            image_id = process_image_id(process)
+        except (AccessDenied, OSError):
+            image_id = ""  # can't tell, keyed by the process alone
+        return self._get_exe_version_cached(process, image_id, version_arg, try_stderr)
+
+    @functools.lru_cache(4096)
+    def _get_exe_version_cached(self, process: Process, image_id: str, version_arg: str, try_stderr: bool) -> str:
+        detection_cache = self._detection_cache
+        if detection_cache is None:
+            return self.get_exe_version(process, version_arg, try_stderr)
+
+        detection = f"version {version_arg}{' (stderr)' if try_stderr else ''}"
+        try:
+            exe_hash = process_exe_hash(process)
+        except OSError:
+            # can't hash the executable, e.g it's gone - detect as usual
+            logger.debug("Failed to hash the executable for the detection cache", pid=process.pid, exc_info=True)
+            return self.get_exe_version(process, version_arg, try_stderr)
+        version = detection_cache.get(exe_hash, detection)
+        if version is None:
+            version = self.get_exe_version(process, version_arg, try_stderr)
+            detection_cache.put(exe_hash, detection, version)
+        return version
 
     def get_metadata(self, process: Process) -> Optional[Dict]:
-        metadata = self._cache.get(process)
+        if feature_flags.is_disabled(APPLICATION_METADATA, process):
+            return None
+
+        try:
+            image_id = process_image_id(process)
+        except (NoSuchProcess, ZombieProcess):
+            raise
+        except Exception:
+            image_id = ""  # can't tell, keep using the cached metadata
+
+        cached = self._cache.get(process)
+        metadata = None
+        replaced = False
+        if cached is not None:
+            cached_image_id, metadata = cached
+            if image_id and cached_image_id != image_id:
+                # same pid & creation time, but a different program (it exec()ed): treat it as a new process.
+                logger.debug(
+                    "Process was replaced by a different program, collecting its metadata again", pid=process.pid
+                )
+                metadata = None
+                replaced = True
+
         if metadata is None:
             if len(self._cache) > self._CACHE_CLEAR_ON_SIZE:
-                self._clear_cache()
+                self.compact_cache()
             try:
                 metadata = self.make_application_metadata(process)
             except (NoSuchProcess, ZombieProcess):
@@ -74,7 +136,12 @@ class ApplicationMetadata:
                     )
                     self._metadata_exception_logs_count += 1
             else:
-                self._cache[process] = metadata
+                if replaced:
+                    # the metadata index is the root frame of the process' stacks - so even if the new program's
+                    # metadata is otherwise the same (e.g the same interpreter running another script), its stacks
+                    # are rooted apart from those of the program it replaced.
+                    metadata["image_id"] = image_id
+                self._cache[process] = (image_id, metadata)
 
         return metadata
 
@@ -0,0 +1,108 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+An on-disk cache of per-executable detection results (e.g the version of a Python or Node interpreter), keyed by
+the hash of the executable file - so restarts of the agent don't repeat the detection work, which adds up on hosts
+running many identical workers.
+"""
+import functools
+import hashlib
+import json
+import os
+import time
+from pathlib import Path
+from threading import Lock
+from typing import Dict, Optional, Tuple
+
+from granulate_utils.linux.process import process_exe
+from psutil import Process
+
+from gprofiler.log import get_logger_adapter
+from gprofiler.platform import is_windows
+from gprofiler.utils.fs import write_file_restricted
+
+logger = get_logger_adapter(__name__)
+
+DETECTION_CACHE_FORMAT_VERSION = 1
+# least recently used entries beyond this are dropped when saving
+MAX_DETECTION_CACHE_ENTRIES = 4096
+_HASH_CHUNK_SIZE = 1024 * 1024
+
+
+@functools.lru_cache(maxsize=1024)
+def _file_hash(path: str, identity: Tuple[int, int, int, int]) -> str:
+    # keyed by the file identity as well, so the (costly) hash is computed once per executable file.
+    sha256 = hashlib.sha256()
+    with open(path, "rb") as f:
+        for chunk in iter(lambda: f.read(_HASH_CHUNK_SIZE), b""):
+            sha256.update(chunk)
+    return sha256.hexdigest()
+
+
+def process_exe_hash(process: Process) -> str:
+    # /proc/<pid>/exe refers to the executable even if it's in another mount namespace.
+    path = process_exe(process) if is_windows() else f"/proc/{process.pid}/exe"
+    st = os.stat(path)
+    return _file_hash(path, (st.st_dev, st.st_ino, st.st_size, st.st_mtime_ns))
+
+
+class DetectionCache:
+    def __init__(self, path: str, world_readable: bool = False) -> None:
+        self._path = Path(path)
+        self._world_readable = world_readable
+        self._lock = Lock()
+        self._dirty = False
+        # "<detection>:<executable hash>" -> {"result", "last_used"}
+        self._entries: Dict[str, Dict] = {}
+        if self._path.exists():
+            try:
+                self._entries = json.loads(self._path.read_text(encoding="utf-8"))["entries"]
+            except Exception:
+                logger.warning("Failed to read the detection cache, starting with an empty one", path=path)
+
+    @property
+    def path(self) -> str:
+        return str(self._path)
+
+    def get(self, exe_hash: str, detection: str) -> Optional[str]:
+        key = f"{detection}:{exe_hash}"
+        with self._lock:
+            entry = self._entries.get(key)
+            if entry is None:
+                return None
+            entry["last_used"] = time.time()
+            self._dirty = True
+            return entry["result"]
+
+    def put(self, exe_hash: str, detection: str, result: str) -> None:
+        key = f"{detection}:{exe_hash}"
+        with self._lock:
+            self._entries[key] = {"result": result, "last_used": time.time()}
+            self._dirty = True
+
+    def save(self) -> None:
+        with self._lock:
+            if not self._dirty:
+                return
+            if len(self._entries) > MAX_DETECTION_CACHE_ENTRIES:
+                by_last_use = sorted(self._entries.items(), key=lambda item: item[1]["last_used"], reverse=True)
+                self._entries = dict(by_last_use[:MAX_DETECTION_CACHE_ENTRIES])
+            content = json.dumps({"version": DETECTION_CACHE_FORMAT_VERSION, "entries": self._entries})
+            self._dirty = False
+        tmp_path = self._path.with_name(self._path.name + ".tmp")
+        write_file_restricted(tmp_path, content.encode("utf-8"), self._world_readable)
+        # replace atomically, so the next run never sees a partially written cache.
+        os.replace(tmp_path, self._path)
@@ -1,5 +1,7 @@
 import array
 import errno
+import hashlib
+import hmac
 import ipaddress
 import os
 import platform
@@ -9,8 +11,10 @@ import struct
 import subprocess
 import sys
 import time
+import uuid
 from dataclasses import dataclass
 from functools import lru_cache
+from pathlib import Path
 from typing import Any, Dict, Optional, Tuple, cast
 
 import distro
@@ -24,12 +28,22 @@ from gprofiler.utils import is_pyinstaller, run_process
 if is_linux():
     import fcntl
 else:
+    import winreg
+
     import netifaces
 
 UNKNOWN_VALUE = "unknown"
 
 logger = get_logger_adapter(__name__)
 hostname: Optional[str] = None
+host_id: Optional[str] = None
+boot_id: Optional[str] = None
+# namespace for the UUIDs we derive (host id from a MAC address, boot id on Windows)
+GPROFILER_UUID_NAMESPACE = uuid.UUID("7d1c5c1e-4a35-4c5e-9a93-6b8d2d9b7f1a")
+# the key of the app-specific host id, derived from the machine-id (see machine_app_specific_id)
+GPROFILER_APP_ID = uuid.UUID("3f6a2c8e-91d4-4b7a-8e25-5c0f1b9d4a63")
+# without a boot id, the boot time (which is derived from the uptime, and jitters) is rounded to this many seconds
+_BOOT_TIME_ROUNDING = 60
 RUN_MODE_TO_DEPLOYMENT_TYPE: Dict[str, str] = {
     "k8s": "k8s",
     "container": "containers",
@@ -151,6 +165,74 @@ def get_mac_address() -> str:
     return UNKNOWN_VALUE
 
 
+def _read_machine_id() -> str:
+    if is_windows():
+        with winreg.OpenKey(
+            winreg.HKEY_LOCAL_MACHINE, r"SOFTWARE\Microsoft\Cryptography", 0, winreg.KEY_READ | winreg.KEY_WOW64_64KEY
+        ) as key:
+            machine_guid, _ = winreg.QueryValueEx(key, "MachineGuid")
+            return str(machine_guid)
+
+    # read in the host mount NS, so we get the host's machine-id and not the one of our container.
+    return cast(str, run_in_ns_wrapper(["mnt"], lambda: Path("/etc/machine-id").read_text().strip()))
+
+
+def machine_app_specific_id(machine_id: str) -> str:
+    """
+    The machine-id is confidential (see machine-id(5)), so it's never sent as is - like
+    sd_id128_get_machine_app_specific(), the host id is an HMAC of it keyed with an id of gProfiler, formatted as a
+    v4 UUID. It's as stable as the machine-id, but can't be correlated with it.
+    """
+    digest = hmac.new(GPROFILER_APP_ID.bytes, machine_id.encode(), hashlib.sha256).digest()
+    return str(uuid.UUID(bytes=digest[:16], version=4))
+
+
+def _read_windows_boot_id(host_id: str) -> str:
+    try:
+        # incremented by Windows on every boot
+        with winreg.OpenKey(
+            winreg.HKEY_LOCAL_MACHINE,
+            r"SYSTEM\CurrentControlSet\Control\Session Manager\Memory Management\PrefetchParameters",
+            0,
+            winreg.KEY_READ | winreg.KEY_WOW64_64KEY,
+        ) as key:
+            boot_count, _ = winreg.QueryValueEx(key, "BootId")
+        boot = f"boot-count:{boot_count}"
+    except OSError:
+        boot = f"boot-time:{round(psutil.boot_time() / _BOOT_TIME_ROUNDING)}"
+    return str(uuid.uuid5(GPROFILER_UUID_NAMESPACE, f"{host_id}:{boot}"))
+
+
+def get_host_ids() -> Tuple[str, str]:
+    """
+    Gets a stable identifier of this host, and an identifier of its current boot session.
+    The host id is derived from the machine-id (MachineGuid on Windows), which is regenerated when the machine is
+    reimaged; if unavailable, it is derived from the MAC address. The boot id changes on every boot of the host.
+    """
+    try:
+        machine_id = _read_machine_id()
+        if not machine_id:
+            raise ValueError("empty machine id")
+        current_host_id = machine_app_specific_id(machine_id)
+    except Exception:
+        logger.exception("Failed to get the machine id, deriving the host id from the MAC address")
+        current_host_id = str(uuid.uuid5(GPROFILER_UUID_NAMESPACE, get_mac_address()))
+
+    try:
+        if is_windows():
+            # Windows has no boot id, so derive one from the boot counter.
+            current_boot_id = _read_windows_boot_id(current_host_id)
+        else:
+            # boot_id is not namespaced, so we get the host's value from within containers as well.
+            with open("/proc/sys/kernel/random/boot_id") as f:
+                current_boot_id = f.read().strip()
+    except Exception:
+        logger.exception("Failed to get the boot id")
+        current_boot_id = UNKNOWN_VALUE
+
+    return current_host_id, current_boot_id
+
+
 def get_cpu_info() -> Tuple[str, str]:
     """
     Parse /proc/cpuinfo to get model name & flags.
@@ -200,6 +282,8 @@ class SystemInfo:
     cpu_flags: str
     memory_capacity_mb: int
     hostname: str
+    host_id: str
+    boot_id: str
     system: str
     os_name: str
     os_release: str
@@ -237,6 +321,8 @@ if is_windows():
 
 
 def get_static_system_info() -> SystemInfo:
+    global host_id, boot_id
+    host_id, boot_id = get_host_ids()
     if is_windows():
         hostname, distribution, libc_tuple, mac_address, local_ip = _initialize_system_info_windows()
         spawn_uptime_ms = time.monotonic() * 1000
@@ -277,6 +363,8 @@ def get_static_system_info() -> SystemInfo:
         cpu_flags=cpu_flags,
         memory_capacity_mb=round(psutil.virtual_memory().total / 1024 / 1024),
         hostname=hostname,
+        host_id=host_id,
+        boot_id=boot_id,
         system=platform.system(),
         os_name=os_name,
         os_release=os_release,
@@ -303,6 +391,14 @@ def get_hostname_or_none() -> Optional[str]:
     return hostname
 
 
+def get_host_id_or_none() -> Optional[str]:
+    return host_id
+
+
+def get_boot_id_or_none() -> Optional[str]:
+    return boot_id
+
+
 def _initialize_system_info_windows() -> Any:
     global hostname
     hostname = f"<{UNKNOWN_VALUE}>"
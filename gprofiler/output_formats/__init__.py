@@ -0,0 +1,11 @@
+# NOTE: Make sure to import any new output formats to register them
+from gprofiler.output_formats.cpuprofile import CpuProfileOutputFormat
+from gprofiler.output_formats.csv_functions import CsvFunctionsByProcessOutputFormat, CsvFunctionsOutputFormat
+from gprofiler.output_formats.speedscope import SpeedscopeOutputFormat
+
+__all__ = [
+    "CpuProfileOutputFormat",
+    "CsvFunctionsOutputFormat",
+    "CsvFunctionsByProcessOutputFormat",
+    "SpeedscopeOutputFormat",
+]
@@ -0,0 +1,79 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+The .cpuprofile format of the Chrome DevTools protocol, which VS Code and the Chrome/Edge DevTools open directly.
+See https://chromedevtools.github.io/devtools-protocol/tot/Profiler/#type-Profile
+"""
+import json
+from typing import Any, Dict, List, Tuple
+
+from gprofiler.analysis.frames import parse_python_frame, strip_frame_suffix
+from gprofiler.gprofiler_types import StackToSampleCount
+from gprofiler.output_formats.output_format import OutputContext, OutputFormat
+from gprofiler.output_formats.registry import register_output_format
+
+ROOT_NODE_ID = 1
+
+
+def _call_frame(frame: str) -> Dict[str, Any]:
+    python_frame = parse_python_frame(frame)
+    if python_frame is not None:
+        function_name, url, line = python_frame.function, python_frame.filename, python_frame.line - 1
+    else:
+        function_name, url, line = strip_frame_suffix(frame), "", -1
+    return {"functionName": function_name, "scriptId": "0", "url": url, "lineNumber": line, "columnNumber": -1}
+
+
+@register_output_format("cpuprofile", ".cpuprofile")
+class CpuProfileOutputFormat(OutputFormat):
+    def write(self, stacks: StackToSampleCount, context: OutputContext) -> bytes:
+        nodes: List[Dict[str, Any]] = [
+            {"id": ROOT_NODE_ID, "callFrame": _call_frame("(root)"), "hitCount": 0, "children": []}
+        ]
+        node_ids: Dict[Tuple[int, str], int] = {}
+        samples = []
+        # the process name is the first frame, so the profiles of each process are in their own subtree.
+        for stack, count in stacks.items():
+            node_id = ROOT_NODE_ID
+            for frame in stack.split(";"):
+                child_id = node_ids.get((node_id, frame))
+                if child_id is None:
+                    child_id = len(nodes) + 1
+                    node_ids[(node_id, frame)] = child_id
+                    nodes.append({"id": child_id, "callFrame": _call_frame(frame), "hitCount": 0, "children": []})
+                    nodes[node_id - 1]["children"].append(child_id)
+                node_id = child_id
+            nodes[node_id - 1]["hitCount"] += count
+            samples.extend([node_id] * count)
+
+        # the samples are ordered by stack rather than by time, so the timeline view isn't meaningful - but the
+        # aggregated (bottom-up, call tree) views are.
+        start_time_us = int(context.start_time.timestamp() * 1_000_000)
+        duration_us = int((context.end_time - context.start_time).total_seconds() * 1_000_000)
+        if context.sampling_frequency:
+            interval_us = 1_000_000 / context.sampling_frequency
+        else:
+            interval_us = duration_us / len(samples) if samples else 0
+        time_deltas = [int((i + 1) * interval_us) - int(i * interval_us) for i in range(len(samples))]
+
+        cpuprofile = {
+            "nodes": nodes,
+            "startTime": start_time_us,
+            "endTime": start_time_us + sum(time_deltas),
+            "samples": samples,
+            "timeDeltas": time_deltas,
+        }
+        return json.dumps(cpuprofile).encode("utf-8")
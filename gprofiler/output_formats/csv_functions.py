@@ -0,0 +1,95 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+import csv
+import io
+from collections import Counter
+from typing import Counter as CounterType
+from typing import Dict, Tuple
+
+from gprofiler.analysis.frames import parse_python_frame, strip_frame_suffix
+from gprofiler.gprofiler_types import StackToSampleCount
+from gprofiler.output_formats.output_format import OutputContext, OutputFormat, OutputFormatOptionError
+from gprofiler.output_formats.registry import register_output_format
+
+
+def _function_and_module(frame: str) -> Tuple[str, str]:
+    python_frame = parse_python_frame(frame)
+    if python_frame is not None:
+        return python_frame.function, python_frame.filename
+    return strip_frame_suffix(frame), ""
+
+
+@register_output_format("csv-functions", ".functions.csv")
+class CsvFunctionsOutputFormat(OutputFormat):
+    """
+    A table of the self & total samples of each function, to be opened in a spreadsheet. Self samples are those in
+    which the function is the leaf frame; total samples are those in which it appears anywhere in the stack.
+    """
+
+    by_process = False
+    options = {"sort": "Sort the functions by their 'total' (default) or 'self' samples"}
+
+    @classmethod
+    def parse_options(cls, options: Dict[str, str]) -> Dict[str, str]:
+        options = super().parse_options(options)
+        if options.get("sort", "total") not in ("total", "self"):
+            raise OutputFormatOptionError(f"Invalid {cls.name} sort {options['sort']!r}, expected 'total' or 'self'")
+        return options
+
+    def write(self, stacks: StackToSampleCount, context: OutputContext) -> bytes:
+        self_samples: CounterType[Tuple[str, ...]] = Counter()
+        total_samples: CounterType[Tuple[str, ...]] = Counter()
+        for stack, count in stacks.items():
+            comm, *frames = stack.split(";")
+            prefix = (comm,) if self.by_process else ()
+            keys = [prefix + _function_and_module(frame) for frame in frames]
+            if not keys:
+                continue
+            self_samples[keys[-1]] += count
+            # a function appearing multiple times in a stack (recursion) is counted once.
+            for key in set(keys):
+                total_samples[key] += count
+
+        samples_count = sum(stacks.values()) or 1
+        output = io.StringIO()
+        writer = csv.writer(output, lineterminator="\n")
+        header = ["function", "module", "self_samples", "total_samples", "self_percent", "total_percent"]
+        writer.writerow((["process"] if self.by_process else []) + header)
+        if self._options.get("sort") == "self":
+            order = sorted(total_samples.items(), key=lambda item: (-self_samples[item[0]], -item[1], item[0]))
+        else:
+            order = sorted(total_samples.items(), key=lambda item: (-item[1], -self_samples[item[0]], item[0]))
+        for key, total in order:
+            self_count = self_samples[key]
+            writer.writerow(
+                [
+                    *key,
+                    self_count,
+                    total,
+                    f"{self_count * 100 / samples_count:.2f}",
+                    f"{total * 100 / samples_count:.2f}",
+                ]
+            )
+        return output.getvalue().encode("utf-8")
+
+
+@register_output_format("csv-functions-by-process", ".functions-by-process.csv")
+class CsvFunctionsByProcessOutputFormat(CsvFunctionsOutputFormat):
+    """
+    Like csv-functions, with a row for each function in each process (by process name).
+    """
+
+    by_process = True
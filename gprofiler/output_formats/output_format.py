@@ -0,0 +1,64 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+import datetime
+from abc import ABC, abstractmethod
+from dataclasses import dataclass
+from typing import Dict, Optional
+
+from gprofiler.gprofiler_types import StackToSampleCount
+
+
+class OutputFormatOptionError(Exception):
+    pass
+
+
+@dataclass
+class OutputContext:
+    start_time: datetime.datetime
+    end_time: datetime.datetime
+    # None in allocation profiling
+    sampling_frequency: Optional[int]
+
+
+class OutputFormat(ABC):
+    """
+    An additional output format, written next to the collapsed file of each session (with --output-dir).
+    Output formats get the stacks of the session as in the flamegraph: "comm;frame;frame..." -> count, without the
+    container names & application metadata.
+    """
+
+    name: str
+    file_suffix: str
+    # the options accepted with --format-opt, and their descriptions
+    options: Dict[str, str] = {}
+
+    def __init__(self, options: Optional[Dict[str, str]] = None) -> None:
+        self._options = self.parse_options(options or {})
+
+    @classmethod
+    def parse_options(cls, options: Dict[str, str]) -> Dict[str, str]:
+        """
+        Validates the options given to this format, raising OutputFormatOptionError for invalid ones. Formats with
+        options whose values must be validated extend it.
+        """
+        unknown = sorted(set(options) - set(cls.options))
+        if unknown:
+            raise OutputFormatOptionError(f"Unknown {cls.name} option(s): {', '.join(unknown)}")
+        return options
+
+    @abstractmethod
+    def write(self, stacks: StackToSampleCount, context: OutputContext) -> bytes:
+        raise NotImplementedError
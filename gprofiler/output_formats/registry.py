@@ -0,0 +1,63 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+from typing import Any, Dict, List, Type
+
+from gprofiler.output_formats.output_format import OutputFormat, OutputFormatOptionError
+
+output_formats: Dict[str, Type[OutputFormat]] = {}
+
+
+def register_output_format(name: str, file_suffix: str) -> Any:
+    def output_format_decorator(output_format_class: Type[OutputFormat]) -> Type[OutputFormat]:
+        assert name not in output_formats, f"{name} is already registered!"
+        output_format_class.name = name
+        output_format_class.file_suffix = file_suffix
+        output_formats[name] = output_format_class
+        return output_format_class
+
+    return output_format_decorator
+
+
+def get_output_formats_registry() -> Dict[str, Type[OutputFormat]]:
+    return output_formats
+
+
+def parse_format_options(format_opts: List[str], format_names: List[str]) -> Dict[str, Dict[str, str]]:
+    """
+    Splits the --format-opt values ("key=value", or "format.key=value" to address a single format) among the
+    selected output formats, and validates them. Unprefixed keys go to all of the selected formats accepting them.
+    Returns format name -> options.
+    """
+    options: Dict[str, Dict[str, str]] = {name: {} for name in format_names}
+    for format_opt in format_opts:
+        key, sep, value = format_opt.partition("=")
+        if not sep or not key:
+            raise OutputFormatOptionError(f"Invalid format option {format_opt!r}, expected key=value")
+        format_name, dot, key = key.rpartition(".")
+        if dot:
+            if format_name not in options:
+                raise OutputFormatOptionError(f"Format option {format_opt!r} is of a format which isn't selected")
+            options[format_name][key] = value
+            continue
+        accepting = [name for name in format_names if key in output_formats[name].options]
+        if not accepting:
+            raise OutputFormatOptionError(f"None of the selected output formats accepts the option {key!r}")
+        for name in accepting:
+            options[name][key] = value
+
+    for name, format_options in options.items():
+        output_formats[name].parse_options(format_options)
+    return options
@@ -0,0 +1,89 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+The speedscope (https://www.speedscope.app) file format, with a "sampled" profile per process name.
+See https://github.com/jlfwong/speedscope/wiki/Importing-from-custom-sources
+The stacks are aggregated, so there's no "evented" (timeline) profile to emit - speedscope's "Left Heavy" and
+"Sandwich" views are the meaningful ones.
+"""
+import json
+from typing import Any, Dict, List
+
+from gprofiler import __version__
+from gprofiler.analysis.frames import parse_python_frame, strip_frame_suffix
+from gprofiler.gprofiler_types import StackToSampleCount
+from gprofiler.output_formats.output_format import OutputContext, OutputFormat
+from gprofiler.output_formats.registry import register_output_format
+from gprofiler.utils import get_iso8601_format_time
+
+SPEEDSCOPE_SCHEMA = "https://www.speedscope.app/file-format-schema.json"
+
+
+def _frame(frame: str) -> Dict[str, Any]:
+    python_frame = parse_python_frame(frame)
+    if python_frame is not None:
+        return {"name": python_frame.function, "file": python_frame.filename, "line": python_frame.line}
+    return {"name": strip_frame_suffix(frame)}
+
+
+@register_output_format("speedscope", ".speedscope.json")
+class SpeedscopeOutputFormat(OutputFormat):
+    options = {"name": "The name of the profile shown by speedscope (default: 'gProfiler <start time>')"}
+
+    def write(self, stacks: StackToSampleCount, context: OutputContext) -> bytes:
+        frames: List[Dict[str, Any]] = []
+        frame_indices: Dict[str, int] = {}
+        profiles: Dict[str, Dict[str, Any]] = {}
+        # with a sampling frequency, the weights are in seconds of CPU time; otherwise they're the sample counts.
+        unit = "seconds" if context.sampling_frequency else "none"
+
+        for stack, count in stacks.items():
+            comm, _, rest = stack.partition(";")
+            sample = []
+            for frame in rest.split(";") if rest else []:
+                index = frame_indices.get(frame)
+                if index is None:
+                    index = frame_indices[frame] = len(frames)
+                    frames.append(_frame(frame))
+                sample.append(index)
+
+            profile = profiles.get(comm)
+            if profile is None:
+                profile = profiles[comm] = {
+                    "type": "sampled",
+                    "name": comm,
+                    "unit": unit,
+                    "startValue": 0,
+                    "endValue": 0,
+                    "samples": [],
+                    "weights": [],
+                }
+            weight = count / context.sampling_frequency if context.sampling_frequency else count
+            profile["samples"].append(sample)
+            profile["weights"].append(weight)
+            profile["endValue"] += weight
+
+        # the heaviest processes first, so speedscope opens the most interesting one.
+        sorted_profiles = sorted(profiles.values(), key=lambda p: p["endValue"], reverse=True)
+        speedscope = {
+            "$schema": SPEEDSCOPE_SCHEMA,
+            "name": self._options.get("name", f"gProfiler {get_iso8601_format_time(context.start_time)}"),
+            "exporter": f"gProfiler {__version__}",
+            "activeProfileIndex": 0,
+            "shared": {"frames": frames},
+            "profiles": sorted_profiles,
+        }
+        return json.dumps(speedscope).encode("utf-8")
@@ -8,8 +8,10 @@ from psutil import Process
 
 if TYPE_CHECKING:
     from gprofiler.containers_client import ContainerNamesClient
+    from gprofiler.error_records import ErrorRecordsWriter
 
 from gprofiler.utils import TemporaryDirectoryWithMode
+from gprofiler.utils.process_filter import ProcessFilter
 
 
 @dataclass
@@ -26,7 +28,12 @@ class ProfilerState:
     container_names_client: Optional[ContainerNamesClient]
     processes_to_profile: Optional[List[Process]]
     max_processes_per_profiler: int
+    # the maximal number of processes a runtime profiler starts profiling in a session, 0 for unlimited
+    max_new_processes_per_session: int
     max_system_processes_for_system_profilers: int
+    process_filter: Optional[ProcessFilter]
+    # where the per-process profiling failures are recorded, with --error-records
+    error_records: Optional[ErrorRecordsWriter] = None
 
     def __post_init__(self) -> None:
         self._temporary_dir = TemporaryDirectoryWithMode(dir=self.storage_dir, mode=0o755)
@@ -18,31 +18,43 @@ import concurrent.futures
 import contextlib
 import logging
 import os
+import re
 import sched
 import time
 from concurrent.futures import ThreadPoolExecutor
 from concurrent.futures._base import Future
 from threading import Lock, Thread
 from types import TracebackType
-from typing import Dict, List, Optional, Tuple, Type, TypeVar
+from typing import Dict, List, Optional, Set, Tuple, Type, TypeVar
 
 import humanfriendly
 from granulate_utils.linux.proc_events import register_exec_callback, unregister_exec_callback
 from granulate_utils.linux.process import is_process_running
 from psutil import NoSuchProcess, Process, ZombieProcess
 
+from gprofiler.consts import CPU_PROFILING_MODE
+from gprofiler.error_records import (
+    PROCESS_EXITED,
+    VERSION_UNSUPPORTED,
+    ErrorRecord,
+    classify_exception,
+    classify_message,
+)
 from gprofiler.exceptions import StopEventSetException
 from gprofiler.gprofiler_types import ProcessToProfileData, ProfileData, ProfilingErrorStack, StackToSampleCount
 from gprofiler.log import get_logger_adapter
 from gprofiler.profiler_state import ProfilerState
 from gprofiler.utils import limit_frequency
-from gprofiler.utils.process import process_comm
+from gprofiler.utils.process import is_process_debugged, process_comm
 
 logger = get_logger_adapter(__name__)
 
 
 T = TypeVar("T", bound="ProfilerInterface")
 
+# the marker stacks of ProfilingErrorStack, e.g "python;[Profiling error: exception CalledProcessError]"
+_ERROR_STACK_FRAME_RE = re.compile(r";\[Profiling (?P<what>[^:]+): (?P<reason>.+)\]$")
+
 
 class ProfilerInterface:
     """
@@ -63,6 +75,20 @@ class ProfilerInterface:
     def stop(self) -> None:
         pass
 
+    def selected_processes(self) -> Optional[List[Process]]:
+        """
+        The processes the next snapshot() would profile (before the per-session limits), as --dry-run reports them.
+        None if the profiler doesn't select processes (e.g system-wide profilers).
+        """
+        return None
+
+    @property
+    def sampling_frequency(self) -> Optional[int]:
+        """
+        The frequency (Hz) the profiler collects samples at, or None if samples aren't time-based.
+        """
+        return None
+
     def __enter__(self: T) -> T:
         self.start()
         return self
@@ -117,6 +143,11 @@ class ProfilerBase(ProfilerInterface):
             f"profiling mode: {profiler_state.profiling_mode}"
         )
 
+    @property
+    def sampling_frequency(self) -> Optional[int]:
+        # in allocation mode, "frequency" is the allocation interval.
+        return self._frequency if self._profiler_state.profiling_mode == CPU_PROFILING_MODE else None
+
 
 class NoopProfiler(ProfilerInterface):
     """
@@ -139,9 +170,115 @@ class ProcessProfilerBase(ProfilerBase):
     process that we wish to profile; then waits for all and returns the result.
     """
 
+    def __init__(
+        self,
+        frequency: int,
+        duration: int,
+        profiler_state: ProfilerState,
+        min_duration: int = 0,
+    ):
+        super().__init__(frequency, duration, profiler_state, min_duration)
+        # the processes skipped in the last session because a debugger was attached to them
+        self._debugged_pids: Set[int] = set()
+        # the processes profiled in the last session, and the new processes waiting to be profiled (with the time
+        # they were first selected), for --max-new-processes-per-session
+        self._profiled_processes: Set[Process] = set()
+        self._waiting_processes: Dict[Process, float] = {}
+        # how many more new processes can be started in this session (spawned ones included), None for unlimited
+        self._new_processes_budget: Optional[int] = None
+
     def _select_processes_to_profile(self) -> List[Process]:
         raise NotImplementedError
 
+    def _limit_new_processes(self, processes: List[Process], max_new_processes: int) -> List[Process]:
+        """
+        Keeps profiling the processes profiled in the last session, and starts profiling up to max_new_processes
+        others - those waiting longest first. The rest are carried over to the next sessions.
+        """
+        now = time.monotonic()
+        new_processes = [process for process in processes if process not in self._profiled_processes]
+        # forget the waiting processes which aren't selected anymore (e.g exited)
+        self._waiting_processes = {process: self._waiting_processes.get(process, now) for process in new_processes}
+        new_processes.sort(key=lambda process: self._waiting_processes[process])
+        started = new_processes[:max_new_processes]
+        for process in started:
+            del self._waiting_processes[process]
+        if self._waiting_processes:
+            logger.info(
+                f"{self.__class__.__name__}: {len(self._waiting_processes)} new processes are left for the next"
+                f" sessions, starting to profile {len(started)} (--max-new-processes-per-session)"
+            )
+
+        selected = [process for process in processes if process in self._profiled_processes] + started
+        self._profiled_processes = set(selected)
+        self._new_processes_budget = max_new_processes - len(started)
+        return selected
+
+    def _take_new_process_budget(self, process: Process) -> bool:
+        """
+        Whether a process spawned during the session can be started within --max-new-processes-per-session.
+        Those which can't are profiled from a later session, once selected by snapshot().
+        """
+        if self._new_processes_budget is None:
+            return True
+        if self._new_processes_budget <= 0:
+            logger.debug(
+                f"{self.__class__.__name__}: not profiling the spawned process {process.pid} in this session"
+                " (--max-new-processes-per-session)"
+            )
+            return False
+        self._new_processes_budget -= 1
+        self._profiled_processes.add(process)
+        return True
+
+    def _pause_for_debugger(self, process: Process, comm: str) -> bool:
+        """
+        Sampling a process while it's being debugged might interfere with the interactive debugging (and the
+        samples of a process stopped in a debugger are meaningless), so such processes are skipped until the
+        debugger detaches.
+        """
+        try:
+            debugged = is_process_debugged(process)
+        except (NoSuchProcess, ZombieProcess):
+            raise
+        except Exception:
+            # can't tell, profile it as usual
+            debugged = False
+
+        if debugged and process.pid not in self._debugged_pids:
+            logger.info(
+                f"{self.__class__.__name__}: a debugger is attached to process {process.pid} ({comm}),"
+                " pausing its profiling"
+            )
+            self._debugged_pids.add(process.pid)
+        elif not debugged and process.pid in self._debugged_pids:
+            logger.info(
+                f"{self.__class__.__name__}: the debugger detached from process {process.pid} ({comm}),"
+                " resuming its profiling"
+            )
+            self._debugged_pids.discard(process.pid)
+        return debugged
+
+    def _record_error(self, pid: int, comm: str, kind: str, exception: Optional[BaseException], message: str) -> None:
+        error_records = self._profiler_state.error_records
+        if error_records is not None:
+            exception_type = type(exception).__name__ if exception is not None else None
+            error_records.write(ErrorRecord(self.name, pid, comm, kind, exception_type, message))
+
+    def _record_error_stack(self, pid: int, comm: str, stacks: StackToSampleCount) -> None:
+        """
+        Records the failures the profilers return as marker stacks (rather than raise). Skipped processes are
+        failures only if their runtime isn't supported.
+        """
+        if not ProfilingErrorStack.is_error_stack(stacks):
+            return
+        m = _ERROR_STACK_FRAME_RE.search(next(iter(stacks)))
+        if m is None:
+            return
+        kind = classify_message(m.group("reason"))
+        if m.group("what") == "error" or (m.group("what") == "skipped" and kind == VERSION_UNSUPPORTED):
+            self._record_error(pid, comm, kind, None, m.group("reason"))
+
     def _wait_for_profiles(self, futures: Dict[Future, Tuple[int, str]]) -> ProcessToProfileData:
         results = {}
         for future in concurrent.futures.as_completed(futures):
@@ -151,11 +288,12 @@ class ProcessProfilerBase(ProfilerBase):
                 assert result is not None
             except StopEventSetException:
                 raise
-            except (NoSuchProcess, ZombieProcess):
+            except (NoSuchProcess, ZombieProcess) as e:
                 logger.debug(
                     f"{self.__class__.__name__}: process went down during profiling {pid} ({comm})",
                     exc_info=True,
                 )
+                self._record_error(pid, comm, PROCESS_EXITED, e, "process went down during profiling")
                 result = ProfileData(
                     self._profiling_error_stack("error", "process went down during profiling", comm),
                     None,
@@ -164,12 +302,15 @@ class ProcessProfilerBase(ProfilerBase):
                 )
             except Exception as e:
                 logger.exception(f"{self.__class__.__name__}: failed to profile process {pid} ({comm})")
+                self._record_error(pid, comm, classify_exception(e), e, str(e))
                 result = ProfileData(
                     self._profiling_error_stack("error", f"exception {type(e).__name__}", comm),
                     None,
                     None,
                     None,
                 )
+            else:
+                self._record_error_stack(pid, comm, result.stacks)
 
             results[pid] = result
 
@@ -258,7 +399,7 @@ class ProcessProfilerBase(ProfilerBase):
         # do here in that case :/
         return ProfilingErrorStack(what, reason, comm)
 
-    def snapshot(self) -> ProcessToProfileData:
+    def selected_processes(self) -> List[Process]:
         processes_to_profile = self._select_processes_to_profile()
         logger.debug(f"{self.__class__.__name__}: selected {len(processes_to_profile)} processes to profile")
         if self._profiler_state.processes_to_profile is not None and len(processes_to_profile) > 0:
@@ -267,28 +408,53 @@ class ProcessProfilerBase(ProfilerBase):
             ]
             logger.debug(f"{self.__class__.__name__}: processes left after filtering: {len(processes_to_profile)}")
 
+        process_filter = self._profiler_state.process_filter
+        if process_filter is not None and len(processes_to_profile) > 0:
+            processes_to_profile = [process for process in processes_to_profile if process_filter.matches(process)]
+            logger.debug(
+                f"{self.__class__.__name__}: processes left after the process filter: {len(processes_to_profile)}"
+            )
+        return processes_to_profile
+
+    def snapshot(self) -> ProcessToProfileData:
+        processes_to_profile = self.selected_processes()
+
         # Apply max_processes_per_profiler limit for runtime profilers (not system-wide profilers)
         if self._should_limit_processes() and self._profiler_state.max_processes_per_profiler > 0:
             processes_to_profile = self._get_top_processes_by_cpu(
                 processes_to_profile, self._profiler_state.max_processes_per_profiler
             )
 
+        if self._profiler_state.max_new_processes_per_session > 0:
+            processes_to_profile = self._limit_new_processes(
+                processes_to_profile, self._profiler_state.max_new_processes_per_session
+            )
+
         self._notify_selected_processes(processes_to_profile)
 
         if not processes_to_profile:
             return {}
 
+        paused: ProcessToProfileData = {}
         with ThreadPoolExecutor(max_workers=len(processes_to_profile)) as executor:
             futures: Dict[Future, Tuple[int, str]] = {}
             for process in processes_to_profile:
                 try:
                     comm = process_comm(process)
+                    if self._pause_for_debugger(process, comm):
+                        # a marker, so the pause is visible in the profile
+                        paused[process.pid] = ProfileData(
+                            self._profiling_error_stack("paused", "debugger attached", comm), None, None, None
+                        )
+                        continue
                 except (NoSuchProcess, ZombieProcess):
                     continue
 
                 futures[executor.submit(self._profile_process, process, self._duration, False)] = (process.pid, comm)
 
-            return self._wait_for_profiles(futures)
+            results = self._wait_for_profiles(futures)
+        results.update(paused)
+        return results
 
 
 class SpawningProcessProfilerBase(ProcessProfilerBase):
@@ -300,6 +466,8 @@ class SpawningProcessProfilerBase(ProcessProfilerBase):
     _BACKOFF_INIT = 0.1
     # so we wait up to 1.5 seconds
     _BACKOFF_MAX = 0.8
+    # how often a spawned process paused for a debugger is checked again, so it's resumed once the debugger detaches
+    _DEBUGGER_CHECK_INTERVAL = 1.0
 
     def __init__(
         self,
@@ -424,6 +592,11 @@ class SpawningProcessProfilerBase(ProcessProfilerBase):
             if not self._is_profiling_spawning or not is_process_running(process) or process.ppid() == os.getpid():
                 return
 
+            # the same scoping as the processes selected by snapshot()
+            process_filter = self._profiler_state.process_filter
+            if process_filter is not None and not process_filter.matches(process):
+                return
+
             if self._should_profile_process(process):
                 # check again, with the lock this time
                 with self._submit_lock:
@@ -441,6 +614,13 @@ class SpawningProcessProfilerBase(ProcessProfilerBase):
                             return
 
                         comm = process_comm(process)
+                        if self._pause_for_debugger(process, comm):
+                            self._sched.enter(
+                                self._DEBUGGER_CHECK_INTERVAL, 0, self._check_process, (process, interval)
+                            )
+                            return
+                        if not self._take_new_process_budget(process):
+                            return
                         self._futures[self._threads.submit(self._profile_process, process, int(duration), True)] = (
                             process.pid,
                             comm,
@@ -13,13 +13,15 @@
 # See the License for the specific language governing permissions and
 # limitations under the License.
 #
+import contextlib
+import functools
 import os
 import re
 import signal
 from collections import Counter, defaultdict
 from pathlib import Path
 from subprocess import CompletedProcess
-from typing import Any, Dict, List, Match, Optional, cast
+from typing import Any, Dict, Iterable, List, Match, Optional, cast
 
 from granulate_utils.linux.elf import get_elf_id
 from granulate_utils.linux.ns import get_process_nspid, run_in_ns_wrapper
@@ -30,18 +32,21 @@ from granulate_utils.linux.process import (
     process_exe,
 )
 from granulate_utils.python import _BLACKLISTED_PYTHON_PROCS, DETECTED_PYTHON_PROCESSES_REGEX
-from psutil import NoSuchProcess, Process
+from psutil import AccessDenied, NoSuchProcess, Process
 
+from gprofiler.analysis.wait_reasons import annotate_wait_reasons
 from gprofiler.exceptions import (
     CalledProcessError,
     CalledProcessTimeoutError,
     ProcessStoppedException,
     StopEventSetException,
 )
+from gprofiler.feature_flags import NATIVE_UNWINDING, feature_flags
 from gprofiler.gprofiler_types import (
     ProcessToProfileData,
     ProcessToStackSampleCounters,
     ProfileData,
+    ProfilingErrorStack,
     StackToSampleCount,
     integers_list,
     nonnegative_integer,
@@ -67,6 +72,9 @@ from gprofiler.utils.process import process_comm, search_proc_maps
 logger = get_logger_adapter(__name__)
 
 _module_name_in_stack = re.compile(r"\((?P<module_info>(?P<filename>[^\)]+?\.py):\d+)\)")
+# the CPython DLL (e.g python311.dll), loaded by python.exe and by programs embedding CPython (uwsgi, Blender, ...).
+# python3.dll, the stable ABI forwarder, isn't matched - it's loaded along with the versioned one.
+_WINDOWS_PYTHON_DLL_RE = re.compile(r"(?:^|\\)python3\d+\.dll$", re.IGNORECASE)
 
 
 def _add_versions_to_process_stacks(process: Process, stacks: StackToSampleCount) -> StackToSampleCount:
@@ -196,9 +204,13 @@ class PySpyProfiler(SpawningProcessProfilerBase):
         add_versions: bool,
         python_pyspy_process: List[int],
         min_duration: int = 0,
+        wall_clock: bool = False,
+        line_numbers: bool = False,
     ):
         super().__init__(frequency, duration, profiler_state, min_duration)
         self.add_versions = add_versions
+        self._wall_clock = wall_clock
+        self._line_numbers = line_numbers
         self._metadata = PythonMetadata(self._profiler_state.stop_event)
         self._python_pyspy_process = python_pyspy_process
 
@@ -213,14 +225,19 @@ class PySpyProfiler(SpawningProcessProfilerBase):
             "--nonblocking",
             "--format",
             "raw",
-            "-F",
             "--output",
             output_path,
             "-p",
             str(pid),
             "--full-filenames",
         ]
-        if is_linux():
+        if not self._line_numbers:
+            # aggregate by the first line of the functions, instead of the executing line
+            command += ["-F"]
+        if self._wall_clock:
+            # sample the idle threads as well
+            command += ["--idle"]
+        elif is_linux():
             command += ["--gil"]
         return command
 
@@ -251,7 +268,10 @@ class PySpyProfiler(SpawningProcessProfilerBase):
                 raise StopEventSetException
             except CalledProcessTimeoutError:
                 logger.error(f"Profiling with py-spy timed out on process {process.pid}")
-                raise
+                partial = self._parse_partial_output(local_output_path, comm, "py-spy timed out")
+                if partial is None:
+                    raise
+                return ProfileData(partial, appid, app_metadata, container_name)
             except CalledProcessError as e:
                 assert isinstance(e.stderr, str), f"unexpected type {type(e.stderr)}"
 
@@ -261,23 +281,72 @@ class PySpyProfiler(SpawningProcessProfilerBase):
                 ):
                     logger.debug(f"Profiled process {process.pid} exited before py-spy could start")
                     return ProfileData(
-                        self._profiling_error_stack("error", comm, "process exited before py-spy started"),
+                        self._profiling_error_stack("error", "process exited before py-spy started", comm),
                         appid,
                         app_metadata,
                         container_name,
                     )
-                raise
+
+                partial = self._parse_partial_output(local_output_path, comm, f"py-spy exited with {e.returncode}")
+                if partial is None:
+                    raise
+                logger.warning(
+                    f"py-spy failed on process {process.pid} mid-session, emitting the samples collected so far",
+                    returncode=e.returncode,
+                    stderr=e.stderr,
+                )
+                return ProfileData(partial, appid, app_metadata, container_name)
 
             logger.info(f"Finished profiling process {process.pid} with py-spy")
             parsed = parse_one_collapsed_file(Path(local_output_path), comm)
+            if self._wall_clock:
+                parsed = annotate_wait_reasons(parsed)
             if self.add_versions:
                 parsed = _add_versions_to_process_stacks(process, parsed)
             return ProfileData(parsed, appid, app_metadata, container_name)
 
+    def _parse_partial_output(self, output_path: str, comm: str, reason: str) -> Optional[StackToSampleCount]:
+        """
+        When py-spy fails mid-session (e.g the process was replaced by a different binary), it may have already
+        written the samples collected until then. Those are returned with an error frame, telling the profile
+        is partial; or None if nothing was collected.
+        """
+        if not os.path.exists(output_path):
+            return None
+        stacks = parse_one_collapsed_file(Path(output_path), comm)
+        if not stacks:
+            return None
+        error_stack = self._profiling_error_stack("error", f"partial profile, {reason}", comm)
+        return ProfilingErrorStack.attach_error_to_stacks(stacks, error_stack)
+
+    @staticmethod
+    def _windows_loads_python_dll(process: Process) -> Optional[bool]:
+        """
+        Returns None if the loaded modules of the process can't be read.
+        """
+        try:
+            return any(_WINDOWS_PYTHON_DLL_RE.search(mapping.path) for mapping in process.memory_maps())
+        except (AccessDenied, OSError):
+            return None
+
+    def _windows_python_processes(self) -> List[Process]:
+        """
+        Detects Python by the CPython DLL: this catches hosts embedding it, and skips processes which are only named
+        like Python (e.g the python.exe launchers of venvs, which run the base interpreter in a child process).
+        Processes whose modules can't be read are detected by their name, as before.
+        """
+        processes = []
+        for process in pgrep_exe(""):
+            with contextlib.suppress(NoSuchProcess):
+                loads_python_dll = self._windows_loads_python_dll(process)
+                if loads_python_dll or (loads_python_dll is None and "python" in process.name().lower()):
+                    processes.append(process)
+        return processes
+
     def _select_processes_to_profile(self) -> List[Process]:
         filtered_procs = set()
         if is_windows():
-            all_processes = [x for x in pgrep_exe("python")]
+            all_processes = self._windows_python_processes()
         else:
             all_processes = [x for x in pgrep_maps(DETECTED_PYTHON_PROCESSES_REGEX)]
 
@@ -391,6 +460,24 @@ class PySpyProfiler(SpawningProcessProfilerBase):
             "This provides fine-grained control over PyPerf resource usage independent of system profilers. "
             "Default: %(default)s",
         ),
+        ProfilerArgument(
+            name="--python-wall-clock",
+            dest="python_wall_clock",
+            action="store_true",
+            default=False,
+            help="Profile Python in wall-clock (off-CPU) mode: py-spy samples the blocked threads as well, and their"
+            " stacks get a [wait:io], [wait:lock] or [wait:sleep] frame. py-spy doesn't report the thread state, so"
+            " the reason is guessed from the module (and function) of the innermost Python frame: waits in native"
+            " code such as time.sleep aren't annotated. PyPerf samples on-CPU threads only, so py-spy is used.",
+        ),
+        ProfilerArgument(
+            name="--python-line-numbers",
+            dest="python_line_numbers",
+            action="store_true",
+            default=False,
+            help="Emit the executing line of each Python frame, instead of the first line of its function, for"
+            " line-level flamegraphs. Requires py-spy.",
+        ),
     ],
     supported_profiling_modes=["cpu"],
 )
@@ -412,10 +499,26 @@ class PythonProfiler(ProfilerInterface):
         python_pyspy_process: List[int],
         min_duration: int = 0,
         python_skip_pyperf_profiler_above: int = 0,
+        python_wall_clock: bool = False,
+        python_line_numbers: bool = False,
     ):
         if python_mode == "py-spy":
             python_mode = "pyspy"
 
+        if python_wall_clock:
+            if python_mode == "pyperf":
+                raise Exception("--python-wall-clock requires py-spy, it can't be used with --python-mode=pyperf")
+            if python_mode == "auto":
+                logger.info("--python-wall-clock is given, profiling Python with py-spy rather than PyPerf")
+            python_mode = "pyspy"
+
+        if python_line_numbers:
+            if python_mode == "pyperf":
+                raise Exception("--python-line-numbers requires py-spy, it can't be used with --python-mode=pyperf")
+            if python_mode == "auto":
+                logger.info("--python-line-numbers is given, profiling Python with py-spy rather than PyPerf")
+            python_mode = "pyspy"
+
         assert python_mode in (
             "auto",
             "pyperf",
@@ -441,15 +544,21 @@ class PythonProfiler(ProfilerInterface):
         else:
             self._ebpf_profiler = None
 
+        self._create_pyspy_profiler = functools.partial(
+            PySpyProfiler,
+            frequency,
+            duration,
+            profiler_state,
+            add_versions=python_add_versions,
+            python_pyspy_process=python_pyspy_process,
+            min_duration=min_duration,
+            wall_clock=python_wall_clock,
+            line_numbers=python_line_numbers,
+        )
+        # in auto mode, py-spy is created if PyPerf is unavailable - or later, if a kill switch stops PyPerf
+        self._pyspy_fallback = python_mode == "auto"
         if python_mode == "pyspy" or (self._ebpf_profiler is None and python_mode == "auto"):
-            self._pyspy_profiler: Optional[PySpyProfiler] = PySpyProfiler(
-                frequency,
-                duration,
-                profiler_state,
-                add_versions=python_add_versions,
-                python_pyspy_process=python_pyspy_process,
-                min_duration=min_duration,
-            )
+            self._pyspy_profiler: Optional[PySpyProfiler] = self._create_pyspy_profiler()
         else:
             self._pyspy_profiler = None
 
@@ -496,13 +605,45 @@ class PythonProfiler(ProfilerInterface):
                 if self._pyspy_profiler is None:
                     logger.warning("PyPerf skipped but no py-spy fallback available")
 
+        if self._ebpf_profiler is not None and self._native_unwinding_disabled():
+            logger.warning("Native unwinding is disabled by a kill switch, not starting PyPerf")
+            self._ebpf_profiler = None
+            if self._pyspy_profiler is None and self._pyspy_fallback:
+                self._pyspy_profiler = self._create_pyspy_profiler()
+
         # Start the appropriate profiler
         if self._ebpf_profiler is not None:
             self._ebpf_profiler.start()
         elif self._pyspy_profiler is not None:
             self._pyspy_profiler.start()
 
+    def _python_processes(self) -> Iterable[Process]:
+        yield from pgrep_maps(DETECTED_PYTHON_PROCESSES_REGEX)
+
+    def _native_unwinding_disabled(self) -> bool:
+        # PyPerf profiles all Python processes at once, so a kill switch of any of them stops it altogether.
+        return feature_flags.is_disabled_for_any(NATIVE_UNWINDING, self._python_processes())
+
+    def _apply_native_unwinding_kill_switch(self) -> None:
+        assert self._ebpf_profiler is not None
+        if not self._native_unwinding_disabled():
+            return
+
+        logger.warning("Native unwinding was disabled by a kill switch, stopping PyPerf")
+        self._ebpf_profiler.stop()
+        self._ebpf_profiler = None
+        if self._pyspy_profiler is None and self._pyspy_fallback:
+            self._pyspy_profiler = self._create_pyspy_profiler()
+        if self._pyspy_profiler is not None:
+            logger.info("Falling back to py-spy for Python profiling")
+            self._pyspy_profiler.start()
+        else:
+            logger.warning("No py-spy fallback available (--python-mode=pyperf), Python won't be profiled")
+
     def snapshot(self) -> ProcessToProfileData:
+        if self._ebpf_profiler is not None:
+            self._apply_native_unwinding_kill_switch()
+
         if self._ebpf_profiler is not None:
             try:
                 return self._ebpf_profiler.snapshot()
@@ -516,9 +657,21 @@ class PythonProfiler(ProfilerInterface):
                 )
                 self._ebpf_profiler.start()
                 return {}  # empty this round
-        else:
-            assert self._pyspy_profiler is not None
+        elif self._pyspy_profiler is not None:
             return self._pyspy_profiler.snapshot()
+        return {}
+
+    def selected_processes(self) -> Optional[List[Process]]:
+        # PyPerf profiles all Python processes, py-spy selects them one by one
+        if self._ebpf_profiler is None and self._pyspy_profiler is not None:
+            return self._pyspy_profiler.selected_processes()
+        return None
+
+    @property
+    def sampling_frequency(self) -> Optional[int]:
+        if self._ebpf_profiler is not None:
+            return self._ebpf_profiler.sampling_frequency
+        return self._pyspy_profiler.sampling_frequency if self._pyspy_profiler is not None else None
 
     def stop(self) -> None:
         if self._ebpf_profiler is not None:
@@ -2,7 +2,7 @@ import statistics
 from abc import ABCMeta, abstractmethod
 from dataclasses import dataclass
 from threading import Event, RLock, Thread
-from typing import List, Optional
+from typing import Dict, List, Optional
 
 import psutil
 
@@ -18,6 +18,10 @@ class Metrics:
     cpu_avg: Optional[float]
     # The average RAM usage between gProfiler cycles
     mem_avg: Optional[float]
+    # The number of internal cache entries removed by compaction during this cycle
+    compacted_cache_entries: Optional[int] = None
+    # The share of samples in first-party code under each --first-party-share-endpoint (None if it wasn't sampled)
+    first_party_share: Optional[Dict[str, Optional[float]]] = None
 
 
 class SystemMetricsMonitorBase(metaclass=ABCMeta):
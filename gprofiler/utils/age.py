@@ -0,0 +1,155 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+Encryption to X25519 recipients in the age format (https://age-encryption.org/v1), so the encrypted output files
+can be decrypted with the standard tools, e.g "age --decrypt -i key.txt profile.col.age".
+"""
+
+import base64
+import hashlib
+import hmac
+import os
+from pathlib import Path
+from typing import List
+
+from cryptography.hazmat.primitives import hashes
+from cryptography.hazmat.primitives.asymmetric.x25519 import X25519PrivateKey, X25519PublicKey
+from cryptography.hazmat.primitives.ciphers.aead import ChaCha20Poly1305
+from cryptography.hazmat.primitives.kdf.hkdf import HKDF
+from cryptography.hazmat.primitives.serialization import Encoding, PublicFormat
+
+AGE_VERSION_LINE = b"age-encryption.org/v1"
+X25519_INFO = b"age-encryption.org/v1/X25519"
+RECIPIENT_HRP = "age"
+PAYLOAD_CHUNK_SIZE = 64 * 1024
+# the suffix of encrypted files
+AGE_FILE_SUFFIX = ".age"
+
+_BECH32_CHARSET = "qpzry9x8gf2tvdw0s3jn54khce6mua7l"
+
+
+class InvalidRecipientError(Exception):
+    pass
+
+
+def _bech32_polymod(values: List[int]) -> int:
+    generator = [0x3B6A57B2, 0x26508E6D, 0x1EA119FA, 0x3D4233DD, 0x2A1462B3]
+    checksum = 1
+    for value in values:
+        top = checksum >> 25
+        checksum = (checksum & 0x1FFFFFF) << 5 ^ value
+        for i in range(5):
+            checksum ^= generator[i] if ((top >> i) & 1) else 0
+    return checksum
+
+
+def _bech32_hrp_expand(hrp: str) -> List[int]:
+    return [ord(c) >> 5 for c in hrp] + [0] + [ord(c) & 31 for c in hrp]
+
+
+def _convert_bits(data: List[int], from_bits: int, to_bits: int, pad: bool) -> List[int]:
+    acc = 0
+    bits = 0
+    result = []
+    for value in data:
+        acc = (acc << from_bits) | value
+        bits += from_bits
+        while bits >= to_bits:
+            bits -= to_bits
+            result.append((acc >> bits) & ((1 << to_bits) - 1))
+    if pad and bits > 0:
+        result.append((acc << (to_bits - bits)) & ((1 << to_bits) - 1))
+    elif not pad and (bits >= from_bits or (acc << (to_bits - bits)) & ((1 << to_bits) - 1)):
+        raise InvalidRecipientError("invalid padding")
+    return result
+
+
+def encode_recipient(public_key: bytes) -> str:
+    data = _convert_bits(list(public_key), 8, 5, True)
+    polymod = _bech32_polymod(_bech32_hrp_expand(RECIPIENT_HRP) + data + [0] * 6) ^ 1
+    checksum = [(polymod >> 5 * (5 - i)) & 31 for i in range(6)]
+    return RECIPIENT_HRP + "1" + "".join(_BECH32_CHARSET[d] for d in data + checksum)
+
+
+def decode_recipient(recipient: str) -> bytes:
+    """
+    Decodes an X25519 recipient ("age1...") into its public key.
+    """
+    recipient = recipient.lower()
+    hrp, separator, encoded = recipient.rpartition("1")
+    if not separator or hrp != RECIPIENT_HRP or len(encoded) < 6:
+        raise InvalidRecipientError(f"{recipient!r} is not an age X25519 recipient")
+    try:
+        data = [_BECH32_CHARSET.index(c) for c in encoded]
+    except ValueError:
+        raise InvalidRecipientError(f"{recipient!r} has invalid characters")
+    if _bech32_polymod(_bech32_hrp_expand(hrp) + data) != 1:
+        raise InvalidRecipientError(f"{recipient!r} has an invalid checksum")
+    public_key = bytes(_convert_bits(data[:-6], 5, 8, False))
+    if len(public_key) != 32:
+        raise InvalidRecipientError(f"{recipient!r} has an invalid length")
+    return public_key
+
+
+def read_recipients_file(path: str) -> List[bytes]:
+    """
+    Reads a recipients file - one "age1..." recipient per line, empty lines and "#" comments are ignored.
+    """
+    recipients = []
+    for line in Path(path).read_text().splitlines():
+        line = line.strip()
+        if line and not line.startswith("#"):
+            recipients.append(decode_recipient(line))
+    if not recipients:
+        raise InvalidRecipientError(f"No recipients in {path}")
+    return recipients
+
+
+def _b64encode(data: bytes) -> bytes:
+    return base64.b64encode(data).rstrip(b"=")
+
+
+def _hkdf(key: bytes, salt: bytes, info: bytes) -> bytes:
+    return HKDF(algorithm=hashes.SHA256(), length=32, salt=salt, info=info).derive(key)
+
+
+def _x25519_stanza(file_key: bytes, recipient: bytes) -> bytes:
+    ephemeral = X25519PrivateKey.generate()
+    share = ephemeral.public_key().public_bytes(Encoding.Raw, PublicFormat.Raw)
+    shared_secret = ephemeral.exchange(X25519PublicKey.from_public_bytes(recipient))
+    wrap_key = _hkdf(shared_secret, share + recipient, X25519_INFO)
+    body = ChaCha20Poly1305(wrap_key).encrypt(b"\x00" * 12, file_key, None)
+    # the body is 32 bytes, so it fits in a single (< 64 columns) line.
+    return b"-> X25519 " + _b64encode(share) + b"\n" + _b64encode(body) + b"\n"
+
+
+def encrypt(data: bytes, recipients: List[bytes]) -> bytes:
+    file_key = os.urandom(16)
+    header = AGE_VERSION_LINE + b"\n"
+    for recipient in recipients:
+        header += _x25519_stanza(file_key, recipient)
+    header += b"---"
+    mac = hmac.new(_hkdf(file_key, b"", b"header"), header, hashlib.sha256).digest()
+
+    nonce = os.urandom(16)
+    payload_key = ChaCha20Poly1305(_hkdf(file_key, nonce, b"payload"))
+    chunks = [data[i : i + PAYLOAD_CHUNK_SIZE] for i in range(0, len(data), PAYLOAD_CHUNK_SIZE)] or [b""]
+    payload = b""
+    for counter, chunk in enumerate(chunks):
+        last = counter == len(chunks) - 1
+        payload += payload_key.encrypt(counter.to_bytes(11, "big") + (b"\x01" if last else b"\x00"), chunk, None)
+
+    return header + b" " + _b64encode(mac) + b"\n" + nonce + payload
@@ -62,3 +62,14 @@ def parse_many_collapsed(text: str) -> ProcessToStackSampleCounters:
         logger.warning(f"Got {len(bad_lines)} bad lines when parsing (showing up to 8):\n" + "\n".join(bad_lines[:8]))
 
     return results
+
+
+def insert_frame_after_comm(stacks: StackToSampleCount, frame: str) -> StackToSampleCount:
+    """
+    Inserts 'frame' as the second frame of each stack, right after the process comm.
+    """
+    result: StackToSampleCount = Counter()
+    for stack, count in stacks.items():
+        comm, sep, rest = stack.partition(";")
+        result[f"{comm};{frame}{sep}{rest}"] += count
+    return result
@@ -15,8 +15,10 @@
 #
 
 import errno
+import getpass
 import os
 import shutil
+import tempfile
 from pathlib import Path
 from secrets import token_hex
 from typing import Union
@@ -73,6 +75,79 @@ def escape_filename(filename: str) -> str:
     return filename.replace(":", "-" if is_windows() else ":")
 
 
+# the well-known SID of the BUILTIN\Administrators group
+WINDOWS_ADMINISTRATORS_SID = "*S-1-5-32-544"
+
+
+def restrict_to_owner(path: Union[str, Path]) -> None:
+    """
+    Restricts access to 'path' to the user who created it & the administrators, so profiling data isn't exposed
+    to other users of the machine. On Windows, the inherited ACEs are replaced with an explicit DACL, which
+    directories pass on to the files later created in them.
+    """
+    if is_windows():
+        inheritance = "(OI)(CI)" if os.path.isdir(path) else ""
+        run_process(
+            [
+                "icacls",
+                str(path),
+                "/inheritance:r",
+                "/grant:r",
+                f"{getpass.getuser()}:{inheritance}F",
+                "/grant:r",
+                f"{WINDOWS_ADMINISTRATORS_SID}:{inheritance}F",
+            ],
+            suppress_log=True,
+        )
+    else:
+        # root is the administrator, and bypasses the mode bits anyway.
+        os.chmod(path, 0o700 if os.path.isdir(path) else 0o600)
+
+
+def write_file_restricted(path: Union[str, Path], data: bytes, world_readable: bool = False) -> None:
+    """
+    Writes 'data' into 'path', creating it accessible to its owner & the administrators only (as restrict_to_owner()
+    does) unless 'world_readable' - restricting it after it's written would leave it readable by other users until
+    then. On Windows, it's written in a new restricted directory, passing its DACL on, then moved into place.
+    """
+    if is_windows():
+        if world_readable:
+            Path(path).write_bytes(data)
+            return
+        tmp_dir = tempfile.mkdtemp(prefix=".gprofiler-", dir=os.path.dirname(os.path.abspath(path)))
+        try:
+            restrict_to_owner(tmp_dir)
+            tmp_path = os.path.join(tmp_dir, os.path.basename(path))
+            Path(tmp_path).write_bytes(data)
+            os.replace(tmp_path, path)
+        finally:
+            shutil.rmtree(tmp_dir, ignore_errors=True)
+    else:
+        mode = 0o644 if world_readable else 0o600
+        fd = os.open(path, os.O_WRONLY | os.O_CREAT | os.O_TRUNC, mode)
+        with os.fdopen(fd, "wb") as f:
+            # an existing file keeps its mode, and a new one is created by the umask
+            os.fchmod(fd, mode)
+            f.write(data)
+
+
+def append_file_restricted(path: Union[str, Path], data: bytes, world_readable: bool = False) -> None:
+    """
+    Appends 'data' to 'path' - creating it as write_file_restricted() does, if it doesn't exist.
+    """
+    if is_windows():
+        if not os.path.exists(path):
+            write_file_restricted(path, b"", world_readable)
+        with open(path, "ab") as f:
+            f.write(data)
+    else:
+        mode = 0o644 if world_readable else 0o600
+        fd = os.open(path, os.O_WRONLY | os.O_APPEND | os.O_CREAT, mode)
+        with os.fdopen(fd, "ab") as f:
+            os.fchmod(fd, mode)
+            f.write(data)
+
+
 def is_owned_by_root(path: Path) -> bool:
     statbuf = path.stat()
     return statbuf.st_uid == 0 and statbuf.st_gid == 0
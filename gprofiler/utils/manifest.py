@@ -0,0 +1,85 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+Each profiling session writes a manifest (profile_<timestamp>.manifest.json) listing the files it emitted with
+their sizes & SHA-256 checksums. The manifest is written last, so partially written or corrupted files can be
+detected by the readers instead of being silently ingested.
+"""
+
+import hashlib
+import json
+from pathlib import Path
+from typing import Any, Dict, List, Optional
+
+from gprofiler.utils.age import AGE_FILE_SUFFIX
+
+MANIFEST_FORMAT_VERSION = 1
+MANIFEST_SUFFIX = ".manifest.json"
+# the suffixes of the files emitted in a session, after the session's base name (profile_<timestamp>)
+SESSION_FILE_SUFFIXES = [".col", ".html", ".summary.json"]
+
+
+class ManifestError(Exception):
+    pass
+
+
+def file_sha256(path: str) -> str:
+    sha256 = hashlib.sha256()
+    with open(path, "rb") as f:
+        for block in iter(lambda: f.read(1024 * 1024), b""):
+            sha256.update(block)
+    return sha256.hexdigest()
+
+
+def make_manifest(paths: List[str]) -> Dict[str, Any]:
+    return {
+        "version": MANIFEST_FORMAT_VERSION,
+        "files": [
+            {"name": Path(path).name, "size": Path(path).stat().st_size, "sha256": file_sha256(path)} for path in paths
+        ],
+    }
+
+
+def find_session_manifest(file_path: str) -> Optional[Path]:
+    """
+    Finds the manifest of the session that emitted 'file_path', if there's one.
+    """
+    path = Path(file_path).resolve()  # last_profile.col & co. are links to the session files
+    name = path.name[: -len(AGE_FILE_SUFFIX)] if path.name.endswith(AGE_FILE_SUFFIX) else path.name
+    for suffix in SESSION_FILE_SUFFIXES:
+        if name.endswith(suffix):
+            manifest_path = path.with_name(name[: -len(suffix)] + MANIFEST_SUFFIX)
+            return manifest_path if manifest_path.exists() else None
+    return None
+
+
+def verify_file(manifest_path: Path, file_path: str) -> None:
+    """
+    Verifies 'file_path' matches its entry in the manifest, raising ManifestError otherwise.
+    """
+    manifest = json.loads(manifest_path.read_text())
+    if manifest.get("version") != MANIFEST_FORMAT_VERSION:
+        raise ManifestError(f"Unsupported manifest version {manifest.get('version')} of {manifest_path}")
+
+    path = Path(file_path).resolve()
+    entry = next((entry for entry in manifest["files"] if entry["name"] == path.name), None)
+    if entry is None:
+        raise ManifestError(f"{path} is not listed in its session manifest {manifest_path}")
+    size = path.stat().st_size
+    if size != entry["size"]:
+        raise ManifestError(f"{path} has size {size}, expected {entry['size']} - it's likely partially written")
+    if file_sha256(str(path)) != entry["sha256"]:
+        raise ManifestError(f"{path} doesn't match its SHA-256 checksum in {manifest_path} - it's likely corrupted")
@@ -0,0 +1,63 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+
+import socket
+import struct
+import time
+from typing import Tuple
+
+NTP_PORT = 123
+# seconds between the NTP epoch (1900) and the Unix epoch (1970)
+NTP_EPOCH_OFFSET = 2208988800
+NTP_PACKET_SIZE = 48
+# LI = 0 (no warning), VN = 3, Mode = 3 (client)
+NTP_CLIENT_FLAGS = 0x1B
+
+DEFAULT_CLOCK_SYNC_TIMEOUT = 2.0
+
+
+def parse_ntp_server(server: str) -> Tuple[str, int]:
+    host, sep, port = server.rpartition(":")
+    if not sep or not port.isdigit():
+        return server, NTP_PORT
+    return host, int(port)
+
+
+def _from_ntp_timestamp(data: bytes, offset: int) -> float:
+    seconds, fraction = struct.unpack_from("!II", data, offset)
+    return seconds - NTP_EPOCH_OFFSET + fraction / 2**32
+
+
+def query_clock_offset(server: str, timeout: float = DEFAULT_CLOCK_SYNC_TIMEOUT) -> float:
+    """
+    Queries an NTP server (SNTP, RFC 4330) and returns the offset of the local wall clock from it, in seconds.
+    A positive offset means the local clock is behind the server.
+    """
+    address = parse_ntp_server(server)
+    with socket.socket(socket.AF_INET, socket.SOCK_DGRAM) as s:
+        s.settimeout(timeout)
+        request = struct.pack("!B", NTP_CLIENT_FLAGS) + b"\0" * (NTP_PACKET_SIZE - 1)
+        originate_time = time.time()
+        s.sendto(request, address)
+        response, _ = s.recvfrom(NTP_PACKET_SIZE)
+        destination_time = time.time()
+
+    if len(response) < NTP_PACKET_SIZE:
+        raise ValueError(f"Truncated NTP response from {server} ({len(response)} bytes)")
+
+    receive_time = _from_ntp_timestamp(response, 32)
+    transmit_time = _from_ntp_timestamp(response, 40)
+    return ((receive_time - originate_time) + (transmit_time - destination_time)) / 2
@@ -14,10 +14,12 @@
 # limitations under the License.
 #
 import contextlib
+import ctypes
+import hashlib
 import re
 from typing import Callable, Iterator, Match, Optional
 
-from granulate_utils.linux.process import is_process_running, read_proc_file
+from granulate_utils.linux.process import is_process_running, process_exe, read_proc_file
 from psutil import AccessDenied, NoSuchProcess, Process, process_iter
 
 from gprofiler.platform import is_windows
@@ -27,6 +29,30 @@ def search_proc_maps(process: Process, pattern: str) -> Optional[Match[str]]:
     return re.search(pattern, read_proc_file(process, "maps").decode(), re.MULTILINE)
 
 
+def is_process_debugged(process: Process) -> bool:
+    """
+    Is a debugger attached to the process? On Linux, that's any tracer (ptrace) - gdb, strace, etc.
+    """
+    if is_windows():
+        PROCESS_QUERY_LIMITED_INFORMATION = 0x1000
+        kernel32 = ctypes.windll.kernel32  # type: ignore
+        process_handle = kernel32.OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, False, process.pid)
+        if not process_handle:
+            raise AccessDenied(process.pid)
+        try:
+            debugger_present = ctypes.c_int()
+            if not kernel32.CheckRemoteDebuggerPresent(process_handle, ctypes.byref(debugger_present)):
+                raise AccessDenied(process.pid)
+            return bool(debugger_present.value)
+        finally:
+            kernel32.CloseHandle(process_handle)
+
+    for line in read_proc_file(process, "status").decode().splitlines():
+        if line.startswith("TracerPid:"):
+            return int(line.split(":", 1)[1]) != 0
+    return False
+
+
 def process_comm(process: Process) -> str:
     if is_windows():
         return process.name()
@@ -37,6 +63,15 @@ def process_comm(process: Process) -> str:
         return name_line.split("\t", 1)[1]
 
 
+def process_image_id(process: Process) -> str:
+    """
+    Identifies the program running in a process - its executable & command line. Those change if the process
+    exec()s into another program (e.g re-executing itself after an in-place interpreter upgrade), while its PID and
+    creation time, which psutil uses to identify a Process, remain the same.
+    """
+    return hashlib.sha1(f"{process_exe(process)}\0{' '.join(process.cmdline())}".encode()).hexdigest()
+
+
 def search_for_process(filter: Callable[[Process], bool]) -> Iterator[Process]:
     for proc in process_iter():
         with contextlib.suppress(NoSuchProcess, AccessDenied):
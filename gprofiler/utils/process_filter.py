@@ -0,0 +1,288 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+import ctypes
+import fnmatch
+import os
+import re
+from contextlib import contextmanager
+from dataclasses import dataclass, field
+from typing import Any, Iterator, List, Optional, Pattern
+
+from granulate_utils.linux.process import process_exe
+from psutil import AccessDenied, NoSuchProcess, Process, ZombieProcess
+
+from gprofiler.containers_client import ContainerNamesClient
+from gprofiler.log import get_logger_adapter
+from gprofiler.platform import is_windows
+
+logger = get_logger_adapter(__name__)
+
+
+def process_session_id(process: Process) -> int:
+    if is_windows():
+        session_id = ctypes.c_ulong()
+        if not ctypes.windll.kernel32.ProcessIdToSessionId(process.pid, ctypes.byref(session_id)):  # type: ignore
+            raise AccessDenied(process.pid)
+        return session_id.value
+    return os.getsid(process.pid)
+
+
+@contextmanager
+def _windows_process_token(pid: int) -> Iterator[ctypes.c_void_p]:
+    PROCESS_QUERY_LIMITED_INFORMATION = 0x1000
+    TOKEN_QUERY = 0x8
+    kernel32 = ctypes.windll.kernel32  # type: ignore
+    advapi32 = ctypes.windll.advapi32  # type: ignore
+
+    process_handle = kernel32.OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, False, pid)
+    if not process_handle:
+        raise AccessDenied(pid)
+    try:
+        token = ctypes.c_void_p()
+        if not advapi32.OpenProcessToken(process_handle, TOKEN_QUERY, ctypes.byref(token)):
+            raise AccessDenied(pid)
+        try:
+            yield token
+        finally:
+            kernel32.CloseHandle(token)
+    finally:
+        kernel32.CloseHandle(process_handle)
+
+
+def _windows_process_sid(pid: int) -> str:
+    TOKEN_USER = 1
+    kernel32 = ctypes.windll.kernel32  # type: ignore
+    advapi32 = ctypes.windll.advapi32  # type: ignore
+
+    with _windows_process_token(pid) as token:
+        size = ctypes.c_ulong()
+        advapi32.GetTokenInformation(token, TOKEN_USER, None, 0, ctypes.byref(size))
+        buffer = ctypes.create_string_buffer(size.value)
+        if not advapi32.GetTokenInformation(token, TOKEN_USER, buffer, size, ctypes.byref(size)):
+            raise AccessDenied(pid)
+        # TOKEN_USER starts with the SID_AND_ATTRIBUTES of the user, whose first member is the SID pointer
+        sid = ctypes.c_void_p.from_buffer(buffer)
+        string_sid = ctypes.c_wchar_p()
+        if not advapi32.ConvertSidToStringSidW(sid, ctypes.byref(string_sid)):
+            raise AccessDenied(pid)
+        try:
+            return str(string_sid.value)
+        finally:
+            kernel32.LocalFree(string_sid)
+
+
+def windows_process_elevated(pid: int) -> bool:
+    TOKEN_ELEVATION = 20
+    advapi32 = ctypes.windll.advapi32  # type: ignore
+
+    with _windows_process_token(pid) as token:
+        elevated = ctypes.c_ulong()
+        size = ctypes.c_ulong()
+        if not advapi32.GetTokenInformation(
+            token, TOKEN_ELEVATION, ctypes.byref(elevated), ctypes.sizeof(elevated), ctypes.byref(size)
+        ):
+            raise AccessDenied(pid)
+        return bool(elevated.value)
+
+
+def process_uid(process: Process) -> str:
+    """
+    The effective UID of the process on Linux, or the SID of its user on Windows (e.g "S-1-5-18").
+    """
+    if is_windows():
+        return _windows_process_sid(process.pid)
+    return str(process.uids().effective)
+
+
+def _uid_matches(uid: str, uids: List[str]) -> bool:
+    # SIDs are case-insensitive
+    return uid.upper() in (u.upper() for u in uids)
+
+
+def rootless_restrictions() -> List[str]:
+    """
+    What rootless mode doesn't profile, to report to the user.
+    """
+    restrictions = [f"Only the processes of the current user ({Process().username()}) are profiled"]
+    if is_windows():
+        restrictions.append("Elevated processes are skipped, attaching to them requires administrator privileges")
+    else:
+        restrictions.append("perf may require a permissive kernel.perf_event_paranoid to sample without root")
+    restrictions.append("Processes whose user can't be read are skipped")
+    return restrictions
+
+
+def _user_matches(username: str, users: List[str]) -> bool:
+    # on Windows, psutil returns "DOMAIN\user" - also match the bare user name, so "SYSTEM" matches
+    # "NT AUTHORITY\SYSTEM". Windows user names are case-insensitive.
+    if is_windows():
+        names = {username.lower(), username.rsplit("\\", 1)[-1].lower()}
+        return any(user.lower() in names for user in users)
+    return username in users
+
+
+def _path_matches(path: str, patterns: List[str]) -> bool:
+    # fnmatch's "*" matches path separators as well, so "/opt/app/**" matches everything beneath /opt/app.
+    # it also normalizes the case on Windows.
+    return any(fnmatch.fnmatch(path, pattern) for pattern in patterns)
+
+
+def _cmdline_matches(cmdline: str, patterns: List[Pattern]) -> bool:
+    return any(pattern.search(cmdline) for pattern in patterns)
+
+
+@dataclass
+class ProcessFilter:
+    """
+    Scopes the processes that gProfiler profiles, by their user, UID (SID on Windows), executable path, command line,
+    session ID and container.
+    A process is profiled if it matches all of the given include filters, and none of the exclude filters.
+    In rootless mode, only the processes of the current user which aren't elevated (on Windows) are profiled, as
+    gProfiler can't attach to others.
+    """
+
+    include_users: List[str] = field(default_factory=list)
+    exclude_users: List[str] = field(default_factory=list)
+    include_paths: List[str] = field(default_factory=list)
+    exclude_paths: List[str] = field(default_factory=list)
+    include_sessions: List[int] = field(default_factory=list)
+    exclude_sessions: List[int] = field(default_factory=list)
+    include_uids: List[str] = field(default_factory=list)
+    exclude_uids: List[str] = field(default_factory=list)
+    include_cmdlines: List[Pattern] = field(default_factory=list)
+    exclude_cmdlines: List[Pattern] = field(default_factory=list)
+    include_containers: List[str] = field(default_factory=list)
+    exclude_containers: List[str] = field(default_factory=list)
+    current_user_only: bool = False
+    _container_names_client: Optional[ContainerNamesClient] = field(default=None, init=False, repr=False, compare=False)
+
+    @classmethod
+    def from_args(cls, user_args: Any) -> Optional["ProcessFilter"]:
+        process_filter = cls(
+            include_users=user_args.get("include_users") or [],
+            exclude_users=user_args.get("exclude_users") or [],
+            include_paths=user_args.get("include_paths") or [],
+            exclude_paths=user_args.get("exclude_paths") or [],
+            include_sessions=user_args.get("include_sessions") or [],
+            exclude_sessions=user_args.get("exclude_sessions") or [],
+            include_uids=user_args.get("include_uids") or [],
+            exclude_uids=user_args.get("exclude_uids") or [],
+            include_cmdlines=[re.compile(pattern) for pattern in user_args.get("include_cmdlines") or []],
+            exclude_cmdlines=[re.compile(pattern) for pattern in user_args.get("exclude_cmdlines") or []],
+            include_containers=user_args.get("include_containers") or [],
+            exclude_containers=user_args.get("exclude_containers") or [],
+            current_user_only=bool(user_args.get("rootless")),
+        )
+        return process_filter if process_filter.enabled else None
+
+    @property
+    def enabled(self) -> bool:
+        return any(
+            (
+                self.include_users,
+                self.exclude_users,
+                self.include_paths,
+                self.exclude_paths,
+                self.include_sessions,
+                self.exclude_sessions,
+                self.include_uids,
+                self.exclude_uids,
+                self.include_cmdlines,
+                self.exclude_cmdlines,
+                self.include_containers,
+                self.exclude_containers,
+                self.current_user_only,
+            )
+        )
+
+    def _container_name(self, process: Process) -> str:
+        if self._container_names_client is None:
+            self._container_names_client = ContainerNamesClient()
+        return self._container_names_client.get_container_name(process.pid)
+
+    def _matches(self, process: Process) -> bool:
+        if self.current_user_only:
+            if process.username() != Process().username():
+                return False
+            if is_windows() and windows_process_elevated(process.pid):
+                return False
+
+        if self.include_users or self.exclude_users:
+            username = process.username()
+            if self.include_users and not _user_matches(username, self.include_users):
+                return False
+            if _user_matches(username, self.exclude_users):
+                return False
+
+        if self.include_paths or self.exclude_paths:
+            exe = process_exe(process)
+            if self.include_paths and not _path_matches(exe, self.include_paths):
+                return False
+            if _path_matches(exe, self.exclude_paths):
+                return False
+
+        if self.include_sessions or self.exclude_sessions:
+            session_id = process_session_id(process)
+            if self.include_sessions and session_id not in self.include_sessions:
+                return False
+            if session_id in self.exclude_sessions:
+                return False
+
+        if self.include_uids or self.exclude_uids:
+            uid = process_uid(process)
+            if self.include_uids and not _uid_matches(uid, self.include_uids):
+                return False
+            if _uid_matches(uid, self.exclude_uids):
+                return False
+
+        if self.include_cmdlines or self.exclude_cmdlines:
+            cmdline = " ".join(process.cmdline())
+            if self.include_cmdlines and not _cmdline_matches(cmdline, self.include_cmdlines):
+                return False
+            if _cmdline_matches(cmdline, self.exclude_cmdlines):
+                return False
+
+        if self.include_containers or self.exclude_containers:
+            # processes which don't run in a container have an empty name, and match no pattern
+            container_name = self._container_name(process)
+            if self.include_containers and not _path_matches(container_name, self.include_containers):
+                return False
+            if container_name and _path_matches(container_name, self.exclude_containers):
+                return False
+
+        return True
+
+    def matches(self, process: Process) -> bool:
+        try:
+            return self._matches(process)
+        except (NoSuchProcess, ZombieProcess, AccessDenied, OSError):
+            logger.debug(f"Could not evaluate the process filter of PID {process.pid}", exc_info=True)
+            return self.matches_unknown()
+
+    def matches_unknown(self) -> bool:
+        """
+        Whether to profile a process whose details can't be read. If an include filter is given, the deployment
+        wants to profile only specific processes, so err on the side of not profiling it.
+        """
+        return not (
+            self.include_users
+            or self.include_paths
+            or self.include_sessions
+            or self.include_uids
+            or self.include_cmdlines
+            or self.include_containers
+            or self.current_user_only
+        )
@@ -0,0 +1,39 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+from typing import List
+
+from psutil import AccessDenied, NoSuchProcess, Process, ZombieProcess
+
+
+def get_process_tree(roots: List[Process]) -> List[Process]:
+    """
+    The roots which are still running, and all of their descendants.
+    psutil finds the descendants from a single scan of the process table (a toolhelp snapshot on Windows), instead of
+    querying every PID.
+    """
+    tree: List[Process] = []
+    for root in roots:
+        try:
+            # is_running() also checks the PID wasn't reused
+            if not root.is_running():
+                continue
+            descendants = root.children(recursive=True)
+        except (NoSuchProcess, ZombieProcess, AccessDenied):
+            continue
+        for process in [root] + descendants:
+            if process not in tree:
+                tree.append(process)
+    return tree
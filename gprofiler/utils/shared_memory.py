@@ -0,0 +1,99 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+"""
+Publishes the profile of the last session into a named shared-memory section (a named file mapping on Windows,
+/dev/shm/<name> on Linux), so sidecar readers can consume it without file I/O.
+
+The section starts with a header, followed by the profile (in the collapsed format):
+
+    magic (4 bytes, "GPSM") | version (u32) | sequence (u64) | length (u64) | end time (u64, epoch milliseconds)
+
+all little-endian. The header is a seqlock: the sequence is odd while the profile is being written, so readers
+should read the sequence, the length, end time & profile, then the sequence again - retrying if it was odd or has
+changed in between.
+"""
+
+import mmap
+import os
+import struct
+
+from gprofiler.platform import is_windows
+
+SHARED_MEMORY_MAGIC = b"GPSM"
+SHARED_MEMORY_VERSION = 1
+# magic, version, sequence, length, end time
+HEADER = struct.Struct("<4sIQQQ")
+HEADER_SIZE = HEADER.size
+_SEQUENCE_OFFSET = 8
+_SEQUENCE = struct.Struct("<Q")
+
+
+class SharedMemoryPublisher:
+    def __init__(self, name: str, size: int, world_readable: bool = False):
+        assert size > HEADER.size, f"size {size} is too small"
+        self._name = name
+        self._size = size
+        self._sequence = 0
+        if is_windows():
+            # a pagefile-backed section, which exists as long as a handle to it is open.
+            self._mmap = mmap.mmap(-1, size, tagname=name)
+            self._path = None
+        else:
+            self._path = os.path.join("/dev/shm", name)
+            # /dev/shm is writable by all users - rather than opening whatever is there (e.g a symlink another user
+            # planted, to have us write over a file of theirs choosing), a new file is created in its place.
+            try:
+                os.unlink(self._path)
+            except FileNotFoundError:
+                pass
+            fd = os.open(
+                self._path,
+                os.O_RDWR | os.O_CREAT | os.O_EXCL | os.O_NOFOLLOW,
+                0o644 if world_readable else 0o600,
+            )
+            try:
+                os.ftruncate(fd, size)
+                self._mmap = mmap.mmap(fd, size)
+            finally:
+                os.close(fd)
+        self._mmap[: HEADER.size] = HEADER.pack(SHARED_MEMORY_MAGIC, SHARED_MEMORY_VERSION, 0, 0, 0)
+
+    @property
+    def capacity(self) -> int:
+        return self._size - HEADER.size
+
+    def publish(self, data: bytes, end_time_ms: int) -> bool:
+        """
+        Publishes 'data', replacing the previous one. Returns False if it doesn't fit in the section.
+        """
+        if len(data) > self.capacity:
+            return False
+
+        self._sequence += 1  # odd - a write is in progress
+        self._mmap[_SEQUENCE_OFFSET : _SEQUENCE_OFFSET + _SEQUENCE.size] = _SEQUENCE.pack(self._sequence)
+        self._mmap[HEADER.size : HEADER.size + len(data)] = data
+        self._mmap[_SEQUENCE_OFFSET + _SEQUENCE.size : HEADER.size] = struct.pack("<QQ", len(data), end_time_ms)
+        self._sequence += 1
+        self._mmap[_SEQUENCE_OFFSET : _SEQUENCE_OFFSET + _SEQUENCE.size] = _SEQUENCE.pack(self._sequence)
+        return True
+
+    def close(self) -> None:
+        self._mmap.close()
+        if self._path is not None:
+            try:
+                os.unlink(self._path)
+            except FileNotFoundError:
+                pass
@@ -0,0 +1,89 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+import base64
+import hashlib
+import hmac
+from pathlib import Path
+
+import pytest
+from cryptography.hazmat.primitives.asymmetric.x25519 import X25519PrivateKey, X25519PublicKey
+from cryptography.hazmat.primitives.ciphers.aead import ChaCha20Poly1305
+from cryptography.hazmat.primitives.serialization import Encoding, PublicFormat
+
+from gprofiler.utils.age import (
+    PAYLOAD_CHUNK_SIZE,
+    X25519_INFO,
+    InvalidRecipientError,
+    _hkdf,
+    decode_recipient,
+    encode_recipient,
+    encrypt,
+    read_recipients_file,
+)
+
+
+def b64decode(data: bytes) -> bytes:
+    return base64.b64decode(data + b"=" * (-len(data) % 4))
+
+
+def decrypt(encrypted: bytes, identity: X25519PrivateKey) -> bytes:
+    """
+    A minimal age decryptor (a single X25519 stanza), following https://age-encryption.org/v1
+    """
+    header, _, rest = encrypted.partition(b"\n--- ")
+    mac, _, payload = rest.partition(b"\n")
+    version, stanza, body = header.split(b"\n")
+    assert version == b"age-encryption.org/v1"
+    assert stanza.startswith(b"-> X25519 ")
+    share = b64decode(stanza.split(b" ")[2])
+    recipient = identity.public_key().public_bytes(Encoding.Raw, PublicFormat.Raw)
+    wrap_key = _hkdf(identity.exchange(X25519PublicKey.from_public_bytes(share)), share + recipient, X25519_INFO)
+    file_key = ChaCha20Poly1305(wrap_key).decrypt(b"\x00" * 12, b64decode(body), None)
+    expected_mac = hmac.new(_hkdf(file_key, b"", b"header"), header + b"\n---", hashlib.sha256).digest()
+    assert b64decode(mac) == expected_mac
+
+    nonce, payload = payload[:16], payload[16:]
+    payload_key = ChaCha20Poly1305(_hkdf(file_key, nonce, b"payload"))
+    encrypted_chunk_size = PAYLOAD_CHUNK_SIZE + 16
+    chunks = [payload[i : i + encrypted_chunk_size] for i in range(0, len(payload), encrypted_chunk_size)]
+    data = b""
+    for counter, chunk in enumerate(chunks):
+        last = counter == len(chunks) - 1
+        data += payload_key.decrypt(counter.to_bytes(11, "big") + (b"\x01" if last else b"\x00"), chunk, None)
+    return data
+
+
+@pytest.mark.parametrize("size", [0, 100, PAYLOAD_CHUNK_SIZE, PAYLOAD_CHUNK_SIZE * 2 + 5])
+def test_encrypt_round_trip(size: int) -> None:
+    identity = X25519PrivateKey.generate()
+    recipient = identity.public_key().public_bytes(Encoding.Raw, PublicFormat.Raw)
+    data = bytes(i % 251 for i in range(size))
+
+    assert decrypt(encrypt(data, [recipient]), identity) == data
+
+
+def test_recipients_file(tmp_path: Path) -> None:
+    public_key = X25519PrivateKey.generate().public_key().public_bytes(Encoding.Raw, PublicFormat.Raw)
+    recipient = encode_recipient(public_key)
+    recipients_path = tmp_path / "recipient.pub"
+    recipients_path.write_text(f"# created by age-keygen\n{recipient}\n")
+
+    assert read_recipients_file(str(recipients_path)) == [public_key]
+    # a recipient from the age spec's test vectors
+    assert len(decode_recipient("age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p")) == 32
+
+    with pytest.raises(InvalidRecipientError):
+        decode_recipient(recipient[:-1] + ("q" if recipient[-1] != "q" else "p"))
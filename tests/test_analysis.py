@@ -0,0 +1,445 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+
+"""
+Tests for the analysis passes from gprofiler/analysis/
+"""
+
+import datetime
+import json
+import unittest.mock
+from collections import Counter
+from pathlib import Path
+from typing import Dict
+
+import pytest
+
+from gprofiler.analysis.analyzer import AnalysisOptions, load_plugins, run_analyzers
+from gprofiler.analysis.baseline import BaselineAnalyzer, load_baseline
+from gprofiler.analysis.categories import aggregate_frame_categories, classify_frame, first_party_share
+from gprofiler.analysis.extensions import extension_module, insert_extension_boundaries
+from gprofiler.analysis.findings import Finding, share_confidence_interval
+from gprofiler.analysis.frames import is_stdlib_module_frame, parse_python_frame
+from gprofiler.analysis.hotspots import BUILTIN_HOTSPOTS, ModuleHotspot, find_module_hotspots
+from gprofiler.analysis.imports import aggregate_imports
+from gprofiler.analysis.inventory import CodeInventory
+from gprofiler.analysis.recursion import collapse_cycles, collapse_recursive_stacks
+from gprofiler.analysis.sinks import FunctionShareAlertSink, make_window_summary
+from gprofiler.analysis.unknown_frames import DEFAULT_UNKNOWN_FRAME_PLACEHOLDER, replace_unknown_frames
+from gprofiler.analysis.wait_reasons import annotate_wait_reasons
+from gprofiler.analysis.webhook import WebhookSink
+from gprofiler.gprofiler_types import ProcessToProfileData, ProfileData, ProfilingErrorStack
+
+MAIN_FRAME = "<module> (/app/main.py:10)_[p]"
+HANDLER_FRAME = "handle_request (/app/server.py:42)_[p]"
+LOGGING_INFO_FRAME = "info (/usr/lib/python3.8/logging/__init__.py:1434 [standard-library==3.8.10])_[p]"
+LOGGING_EMIT_FRAME = "emit (/usr/lib/python3.8/logging/__init__.py:1084 [standard-library==3.8.10])_[p]"
+WORK_FRAME = "compute (/app/server.py:60)_[p]"
+RE_COMPILE_FRAME = "compile (/usr/lib/python3.8/re.py:252 [standard-library==3.8.10])_[p]"
+SRE_COMPILE_FRAME = "compile (/usr/lib/python3.8/sre_compile.py:759 [standard-library==3.8.10])_[p]"
+PICKLE_FRAME = "dumps (/usr/lib/python3.8/pickle.py:1040 [standard-library==3.8.10])_[p]"
+
+
+def get_hotspot(name: str) -> ModuleHotspot:
+    return next(hotspot for hotspot in BUILTIN_HOTSPOTS if hotspot.name == name)
+
+
+def make_profiles(stacks: Dict[str, int]) -> ProcessToProfileData:
+    return {1234: ProfileData(Counter(stacks), None, None, None)}
+
+
+@pytest.mark.parametrize(
+    "frame, modules, expected",
+    [
+        pytest.param(LOGGING_EMIT_FRAME, ["logging"], True, id="stdlib-package"),
+        pytest.param("_compile (/usr/lib/python3.8/re.py:304)_[p]", ["re"], True, id="stdlib-module"),
+        pytest.param(r"emit (C:\Python38\Lib\logging\__init__.py:1084)_[p]", ["logging"], True, id="windows-path"),
+        pytest.param("logging.Logger.info (__init__.py:1434)_[p]", ["logging"], True, id="qualified-name"),
+        pytest.param("log (/app/myapp/logging/utils.py:3)_[p]", ["logging"], False, id="app-package"),
+        pytest.param("_PyEval_EvalFrameDefault_[pn]", ["logging"], False, id="native-frame"),
+    ],
+)
+def test_is_stdlib_module_frame(frame: str, modules: list, expected: bool) -> None:
+    assert is_stdlib_module_frame(frame, modules) == expected
+
+
+def test_parse_python_frame() -> None:
+    python_frame = parse_python_frame(LOGGING_INFO_FRAME)
+    assert python_frame is not None
+    assert python_frame.function == "info"
+    assert python_frame.filename == "/usr/lib/python3.8/logging/__init__.py"
+    assert python_frame.line == 1434
+    assert python_frame.package == "standard-library==3.8.10"
+
+
+def test_logging_hotspot_reports_call_sites() -> None:
+    profiles = make_profiles(
+        {
+            f"python;{MAIN_FRAME};{HANDLER_FRAME};{LOGGING_INFO_FRAME};{LOGGING_EMIT_FRAME}": 30,
+            f"python;{MAIN_FRAME};{LOGGING_INFO_FRAME}": 10,
+            f"python;{MAIN_FRAME};{HANDLER_FRAME};{WORK_FRAME}": 60,
+        }
+    )
+
+    findings = find_module_hotspots(profiles, get_hotspot("logging-hotspot"), 0.1)
+
+    assert len(findings) == 1
+    finding = findings[0]
+    assert finding.pid == 1234
+    assert finding.comm == "python"
+    assert finding.samples == 40
+    assert finding.total_samples == 100
+    assert [(call_site.frame, call_site.samples) for call_site in finding.call_sites] == [
+        (HANDLER_FRAME, 30),
+        (MAIN_FRAME, 10),
+    ]
+    assert finding.call_sites[0].call_path == [MAIN_FRAME, HANDLER_FRAME]
+
+
+def test_builtin_hotspots() -> None:
+    profiles = make_profiles(
+        {
+            f"python;{MAIN_FRAME};{HANDLER_FRAME};{RE_COMPILE_FRAME};{SRE_COMPILE_FRAME}": 20,
+            f"python;{MAIN_FRAME};{PICKLE_FRAME}": 30,
+            f"python;{MAIN_FRAME};{HANDLER_FRAME};{WORK_FRAME}": 50,
+        }
+    )
+
+    findings = run_analyzers(BUILTIN_HOTSPOTS, profiles, AnalysisOptions(hotspot_threshold=0.1))
+
+    assert [(finding.analyzer, finding.samples) for finding in findings] == [
+        ("regex-hotspot", 20),
+        ("pickle-hotspot", 30),
+    ]
+
+
+def test_logging_hotspot_below_threshold() -> None:
+    profiles = make_profiles(
+        {
+            f"python;{MAIN_FRAME};{LOGGING_INFO_FRAME}": 5,
+            f"python;{MAIN_FRAME};{WORK_FRAME}": 95,
+        }
+    )
+
+    assert run_analyzers(BUILTIN_HOTSPOTS, profiles, AnalysisOptions(hotspot_threshold=0.1)) == []
+
+
+def test_hotspots_ignore_error_stacks() -> None:
+    profiles: ProcessToProfileData = {
+        1234: ProfileData(
+            ProfilingErrorStack("error", "process went down during profiling", "python"), None, None, None
+        )
+    }
+
+    assert run_analyzers(BUILTIN_HOTSPOTS, profiles, AnalysisOptions(hotspot_threshold=0.1)) == []
+
+
+PLUGIN_SOURCE = """
+from gprofiler.analysis.analyzer import Analyzer
+from gprofiler.analysis.findings import Finding
+
+
+class PidAnalyzer(Analyzer):
+    name = "pid"
+
+    def analyze(self, process_profiles, options):
+        return [Finding(self.name, "found", pid, "", 0, 0) for pid in process_profiles]
+
+
+class BrokenAnalyzer(Analyzer):
+    name = "broken"
+
+    def analyze(self, process_profiles, options):
+        raise Exception("broken")
+
+
+def get_analyzers():
+    return [BrokenAnalyzer(), PidAnalyzer()]
+"""
+
+
+def test_plugin_analyzers(tmp_path: Path) -> None:
+    (tmp_path / "plugin.py").write_text(PLUGIN_SOURCE)
+    (tmp_path / "invalid.py").write_text("this isn't Python")
+    (tmp_path / "sinks.py").write_text(
+        "from gprofiler.analysis.sinks import FunctionShareAlertSink\n"
+        "def get_sinks():\n"
+        "    return [FunctionShareAlertSink(0.5)]\n"
+    )
+
+    analyzers, sinks = load_plugins(str(tmp_path))
+    assert [analyzer.name for analyzer in analyzers] == ["broken", "pid"]
+    assert [sink.name for sink in sinks] == ["function-share-alert"]
+
+    # the broken analyzer doesn't prevent the others from running
+    findings = run_analyzers(analyzers, make_profiles({"python;a": 1}), AnalysisOptions(hotspot_threshold=0.1))
+    assert [(finding.analyzer, finding.pid) for finding in findings] == [("pid", 1234)]
+
+
+def test_code_inventory(tmp_path: Path) -> None:
+    inventory_path = tmp_path / "inventory.json"
+    inventory = CodeInventory(str(inventory_path))
+    inventory.update(make_profiles({f"python;{MAIN_FRAME};{HANDLER_FRAME};{WORK_FRAME}": 3}), "2022-01-01T00:00:00")
+    inventory.save()
+
+    # a new run continues the inventory of the previous one
+    inventory = CodeInventory(str(inventory_path))
+    inventory.update(make_profiles({f"python;{MAIN_FRAME};_PyEval_EvalFrameDefault_[pn]": 2}), "2022-01-02T00:00:00")
+    inventory.save()
+
+    functions = json.loads(inventory_path.read_text())["functions"]
+    assert functions["/app/main.py"]["<module>"] == {
+        "first_seen": "2022-01-01T00:00:00",
+        "last_seen": "2022-01-02T00:00:00",
+        "samples": 5,
+    }
+    assert functions["/app/server.py"].keys() == {"handle_request", "compute"}
+    assert inventory.functions_count == 3
+
+
+@pytest.mark.parametrize(
+    "frame, expected",
+    [
+        pytest.param(MAIN_FRAME, "app", id="app"),
+        pytest.param(LOGGING_INFO_FRAME, "stdlib", id="stdlib-package-annotation"),
+        pytest.param("_compile (/usr/lib/python3.8/re.py:304)_[p]", "stdlib", id="stdlib-path"),
+        pytest.param(r"emit (C:\Python38\Lib\logging\__init__.py:1084)_[p]", "stdlib", id="stdlib-windows-path"),
+        pytest.param(
+            "get (/venv/lib/python3.8/site-packages/requests/api.py:76 [requests==2.28.1])_[p]",
+            "third-party",
+            id="third-party-package-annotation",
+        ),
+        pytest.param("get (/usr/lib/python3/dist-packages/requests/api.py:76)_[p]", "third-party", id="dist-packages"),
+        pytest.param("_PyEval_EvalFrameDefault_[pn]", None, id="native-frame"),
+    ],
+)
+def test_classify_frame(frame: str, expected: str) -> None:
+    assert classify_frame(frame) == expected
+
+
+def test_aggregate_frame_categories() -> None:
+    profiles = make_profiles(
+        {
+            f"python;{MAIN_FRAME};{HANDLER_FRAME};{LOGGING_INFO_FRAME}": 10,
+            # native frames are attributed to the Python code calling them
+            f"python;{MAIN_FRAME};{WORK_FRAME};_PyEval_EvalFrameDefault_[pn]": 30,
+            "python;_start;Py_RunMain_[pn]": 5,
+        }
+    )
+
+    assert aggregate_frame_categories(profiles) == {"app": 30, "third-party": 0, "stdlib": 10, "other": 5}
+
+
+def test_aggregate_imports() -> None:
+    find_and_load = "_find_and_load (<frozen importlib._bootstrap>:1007)_[p]"
+    exec_module = "exec_module (<frozen importlib._bootstrap_external>:850)_[p]"
+    app_module = "<module> (/app/app.py:1)_[p]"
+    models_module = "<module> (/app/models.py:3)_[p]"
+    profiles = make_profiles(
+        {
+            # the main script's module-level code isn't an import
+            f"python;{app_module};{WORK_FRAME}": 50,
+            f"python;{app_module};{find_and_load};{exec_module};{models_module};{WORK_FRAME}": 20,
+            f"python;{app_module};{find_and_load};acquire (<frozen importlib._bootstrap>:100)_[p]": 5,
+            # importing a module from the module-level code of another
+            f"python;{app_module};{find_and_load};{exec_module};{models_module};{find_and_load};"
+            "get_code (<frozen importlib._bootstrap_external>:916)_[p];_PyEval_EvalFrameDefault_[pn]": 3,
+        }
+    )
+
+    imports = aggregate_imports(profiles)
+    assert imports.phases == {"import_lock": 5, "module_init": 20, "import_machinery": 3}
+    assert imports.modules == {"/app/models.py": 20}
+
+
+def test_first_party_share() -> None:
+    profiles = make_profiles(
+        {
+            f"python;{MAIN_FRAME};{HANDLER_FRAME};{LOGGING_INFO_FRAME}": 10,
+            f"python;{MAIN_FRAME};{HANDLER_FRAME};{WORK_FRAME}": 30,
+            f"python;{MAIN_FRAME};{WORK_FRAME}": 60,
+        }
+    )
+
+    assert first_party_share(profiles, "handle_*") == 0.75
+    assert first_party_share(profiles, "no_such_endpoint") is None
+
+
+def test_replace_unknown_frames() -> None:
+    profiles = make_profiles(
+        {
+            "python;_start;[unknown];[unknown]": 10,
+            "python;_start;(/usr/lib/libfoo.so);do_syscall_64_[k]": 5,
+            f"python;{MAIN_FRAME}": 1,
+        }
+    )
+    reason_samples = {"no-mapping": 0, "no-symbol": 0}
+
+    replace_unknown_frames(profiles, DEFAULT_UNKNOWN_FRAME_PLACEHOLDER, reason_samples)
+
+    assert profiles[1234].stacks == Counter(
+        {
+            "python;_start;[unknown:no-mapping];[unknown:no-mapping]": 10,
+            "python;_start;(/usr/lib/libfoo.so);do_syscall_64_[k]": 5,
+            f"python;{MAIN_FRAME}": 1,
+        }
+    )
+    # samples are counted once per reason, however many unknown frames they have
+    assert reason_samples == {"no-mapping": 10, "no-symbol": 5}
+
+
+def test_collapse_recursion() -> None:
+    assert collapse_cycles(["main", "a", "b", "a", "b", "a", "b", "c"]) == ["main", "a x3", "b x3", "c"]
+    assert collapse_cycles(["f"] * 1000 + ["g"]) == ["f x1000", "g"]
+    assert collapse_cycles(["a", "b", "c"]) == ["a", "b", "c"]
+
+    stacks = Counter({"python;main;" + ";".join(["f"] * 10): 2, "python;main;f": 3})
+    assert collapse_recursive_stacks(stacks, 5) == {"python;main;f x10": 2, "python;main;f": 3}
+
+
+def test_finding_confidence() -> None:
+    low, high = share_confidence_interval(20, 100)
+    assert low == pytest.approx(0.1334, abs=1e-4)
+    assert high == pytest.approx(0.2888, abs=1e-4)
+    assert share_confidence_interval(0, 0) == (0.0, 1.0)
+
+    # the same 20% share - noise in a short capture, but not in a long one
+    short = Finding("logging-hotspot", "", 1, "python", 3, 15, threshold=0.1)
+    long = Finding("logging-hotspot", "", 1, "python", 3000, 15000, threshold=0.1)
+    assert short.low_confidence
+    assert not long.low_confidence
+    assert short.to_dict()["share_interval"][0] < 0.1
+
+
+def test_annotate_wait_reasons() -> None:
+    stacks = Counter(
+        {
+            "python;main (/app/server.py:10);select (/usr/lib/python3.8/selectors.py:468)": 5,
+            "python;work (/app/server.py:20);wait (/usr/lib/python3.8/threading.py:302)": 3,
+            "python;poll (/app/server.py:30);sleep (/usr/lib/python3.8/asyncio/tasks.py:652)": 2,
+            "python;compute (/app/server.py:40)": 7,
+            # only the standard library's sleep
+            "python;retry (/app/server.py:50);sleep (/app/backoff.py:8)": 1,
+        }
+    )
+    assert annotate_wait_reasons(stacks) == {
+        "python;main (/app/server.py:10);select (/usr/lib/python3.8/selectors.py:468);[wait:io]": 5,
+        "python;work (/app/server.py:20);wait (/usr/lib/python3.8/threading.py:302);[wait:lock]": 3,
+        "python;poll (/app/server.py:30);sleep (/usr/lib/python3.8/asyncio/tasks.py:652);[wait:sleep]": 2,
+        "python;compute (/app/server.py:40)": 7,
+        "python;retry (/app/server.py:50);sleep (/app/backoff.py:8)": 1,
+    }
+
+
+def test_extension_boundaries() -> None:
+    numpy_so = "/usr/lib/python3/dist-packages/numpy/core/_multiarray_umath.cpython-38-x86_64-linux-gnu.so"
+    profiles = make_profiles(
+        {
+            f"python;main (/app/main.py:1)_[p];({numpy_so})_[pn];({numpy_so})_[pn]": 3,
+            "python;main (/app/main.py:1)_[p];(/usr/lib/x86_64-linux-gnu/libc.so.6)_[pn]": 2,
+            "python;main (/app/main.py:1)_[p];fast_path (/opt/app/site-packages/speedups.so)": 1,
+        }
+    )
+    insert_extension_boundaries(profiles)
+    assert profiles[1234].stacks == {
+        f"python;main (/app/main.py:1)_[p];[ext:numpy.core._multiarray_umath];({numpy_so})_[pn];({numpy_so})_[pn]": 3,
+        # not an extension module
+        "python;main (/app/main.py:1)_[p];(/usr/lib/x86_64-linux-gnu/libc.so.6)_[pn]": 2,
+        "python;main (/app/main.py:1)_[p];[ext:speedups];fast_path (/opt/app/site-packages/speedups.so)": 1,
+    }
+    assert extension_module(r"(C:\Python311\DLLs\_sqlite3.pyd)") == "_sqlite3"
+    assert (
+        extension_module(r"(C:\app\Lib\site-packages\pydantic_core\_pydantic_core.cp311-win_amd64.pyd)")
+        == "pydantic_core._pydantic_core"
+    )
+
+
+def test_baseline_regression(tmp_path: Path) -> None:
+    baseline_path = tmp_path / "baseline.col"
+    header = {"application_metadata_enabled": True}
+    baseline_path.write_text(
+        "\n".join(
+            [
+                "# " + json.dumps(header),
+                f"0;;python;{MAIN_FRAME};{HANDLER_FRAME} 9",
+                f"0;;python;{MAIN_FRAME};{LOGGING_INFO_FRAME} 1",
+            ]
+        )
+    )
+    analyzer = BaselineAnalyzer(load_baseline(str(baseline_path)), 0.05)
+    profiles = make_profiles(
+        {
+            f"python;{MAIN_FRAME};{HANDLER_FRAME}": 50,
+            f"python;{MAIN_FRAME};{LOGGING_INFO_FRAME};{LOGGING_EMIT_FRAME}": 50,
+        }
+    )
+    # not in the baseline
+    profiles[5678] = ProfileData(Counter({"java;main_[j]": 10}), None, None, None)
+
+    findings = analyzer.analyze(profiles, AnalysisOptions(hotspot_threshold=0.1))
+    assert [(f.pid, f.samples, f.threshold) for f in findings] == [
+        # emit isn't in the baseline at all, info was in 10% of its samples
+        (1234, 50, 0.05),
+        (1234, 50, pytest.approx(0.15)),
+    ]
+    assert findings[0].message.startswith("emit (/usr/lib/python3.8/logging/__init__.py) is in 50.0% of the samples")
+    assert not any(f.low_confidence for f in findings)
+
+
+def test_window_summary() -> None:
+    start_time = datetime.datetime(2024, 1, 1, 10, 0, 0)
+    end_time = datetime.datetime(2024, 1, 1, 10, 1, 0)
+    profiles = make_profiles({f"python;{MAIN_FRAME};{HANDLER_FRAME}": 2, f"python;{MAIN_FRAME};{WORK_FRAME}": 8})
+    profiles[5678] = ProfileData(ProfilingErrorStack("error", "exception", "java"), None, None, None)
+
+    first = make_window_summary(profiles, None, start_time, end_time)
+    assert first.total_samples == 10
+    assert [(f.function, f.share, f.previous_share) for f in first.top_functions] == [
+        ("compute (/app/server.py)", 0.8, None),
+        ("handle_request (/app/server.py)", 0.2, None),
+    ]
+    assert first.top_deltas == []
+    assert first.error_counts == {"[Profiling error: exception]": 1}
+
+    second = make_window_summary(
+        make_profiles({f"python;{MAIN_FRAME};{HANDLER_FRAME}": 5}), first.function_shares, start_time, end_time
+    )
+    assert [(f.function, f.delta) for f in second.top_deltas] == [
+        ("handle_request (/app/server.py)", pytest.approx(0.8)),
+        ("compute (/app/server.py)", pytest.approx(-0.8)),
+    ]
+    FunctionShareAlertSink(0.5).on_window(second)
+
+
+def test_webhook_sink() -> None:
+    time = datetime.datetime(2024, 1, 1, 10, 0, 0)
+    finding = Finding("logging-hotspot", "logging", 1234, "python", 5, 10)
+    summary = make_window_summary(
+        make_profiles({f"python;{MAIN_FRAME};{WORK_FRAME}": 10}), None, time, time, findings=[finding]
+    )
+    with unittest.mock.patch("requests.post") as post:
+        sink = WebhookSink("https://hooks.example.com/abc", findings_only=True, top_functions=1)
+        sink.on_window(summary)
+        # no findings
+        sink.on_window(make_window_summary(make_profiles({}), None, time, time))
+        # waits for the pending summaries to be POSTed
+        sink.close()
+
+    assert post.call_count == 1
+    payload = post.call_args.kwargs["json"]
+    assert payload["findings"] == [finding.to_dict()]
+    assert payload["top_functions"] == [{"function": "compute (/app/server.py)", "share": 1.0}]
+    assert payload["total_samples"] == 10
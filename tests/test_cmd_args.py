@@ -0,0 +1,38 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+import sys
+from typing import List
+
+import pytest
+from pytest import MonkeyPatch
+
+from gprofiler.exit_codes import EXIT_CONFIG_ERROR
+from gprofiler.main import parse_cmd_args
+
+
+def parse_args_error(monkeypatch: MonkeyPatch, capsys: pytest.CaptureFixture, args: List[str]) -> str:
+    monkeypatch.setattr(sys, "argv", ["gprofiler"] + args)
+    with pytest.raises(SystemExit) as e:
+        parse_cmd_args()
+    assert e.value.code == EXIT_CONFIG_ERROR
+    return capsys.readouterr().err
+
+
+def test_heartbeat_server_requires_token(monkeypatch: MonkeyPatch, capsys: pytest.CaptureFixture) -> None:
+    error = parse_args_error(
+        monkeypatch, capsys, ["--enable-heartbeat-server", "--upload-results", "--service-name", "service"]
+    )
+    assert "--token" in error
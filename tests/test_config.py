@@ -0,0 +1,47 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+from pathlib import Path
+
+import pytest
+
+from gprofiler.config import ENV_VAR_PREFIX, ArgumentParser, parse_config_args
+from gprofiler.exit_codes import EXIT_CONFIG_ERROR
+
+
+def make_parser(config_path: Path) -> ArgumentParser:
+    parser = ArgumentParser(auto_env_var_prefix=ENV_VAR_PREFIX, default_config_files=[str(config_path)])
+    parser.add_argument("--frequency", type=int, default=1)
+    parser.add_argument("--duration", type=int, default=1)
+    parser.add_argument("--service-name", type=str, default="default")
+    return parser
+
+
+def test_config_precedence(tmp_path: Path) -> None:
+    config_path = tmp_path / "config.ini"
+    config_path.write_text("frequency = 2\nduration = 2\n")
+    env_vars = {"GPROFILER_FREQUENCY": "3", "GPROFILER_DURATION": "3", "GPROFILER_SERVICE_NAME": "from-env"}
+
+    args = parse_config_args(make_parser(config_path), ["--duration", "4"], env_vars)
+    # the command line overrides the config file, which overrides the environment
+    assert args.duration == 4
+    assert args.frequency == 2
+    assert args.service_name == "from-env"
+
+
+def test_invalid_args_exit_code(tmp_path: Path) -> None:
+    with pytest.raises(SystemExit) as e:
+        parse_config_args(make_parser(tmp_path / "config.ini"), ["--frequency", "often"], {})
+    assert e.value.code == EXIT_CONFIG_ERROR
@@ -0,0 +1,52 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+import json
+
+import pytest
+
+from gprofiler.deprecations import STDERR_DEPRECATION_KEY, find_deprecated_options, report_deprecated_options
+
+
+def test_deprecated_options(capsys: pytest.CaptureFixture) -> None:
+    user_args = {
+        "spark_sample_period": 120,
+        "collect_spark_metrics": True,
+        "databricks_job_name_as_service_name": False,
+        "glogger_server": "https://api.example.com",
+    }
+    deprecations = find_deprecated_options(user_args)
+    assert deprecations == [
+        {
+            "option": "--collect-spark-metrics",
+            "removed_in": "1.42.0",
+            "replacement": None,
+            "message": "--collect-spark-metrics is deprecated and removed in version 1.42.0",
+        },
+        {
+            "option": "--glogger-server",
+            "removed_in": None,
+            "replacement": "--api-server",
+            "message": "--glogger-server is deprecated, use --api-server instead",
+        },
+    ]
+
+    report_deprecated_options(deprecations)
+    # stderr may have the log warnings as well
+    lines = [line for line in capsys.readouterr().err.splitlines() if STDERR_DEPRECATION_KEY in line]
+    assert [json.loads(line)[STDERR_DEPRECATION_KEY]["option"] for line in lines] == [
+        "--collect-spark-metrics",
+        "--glogger-server",
+    ]
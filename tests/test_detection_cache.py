@@ -0,0 +1,42 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+import json
+from pathlib import Path
+
+from psutil import Process
+
+from gprofiler.metadata.detection_cache import DetectionCache, process_exe_hash
+
+
+def test_detection_cache(tmp_path: Path) -> None:
+    cache_path = tmp_path / "detection_cache.json"
+    exe_hash = process_exe_hash(Process())
+    cache = DetectionCache(str(cache_path))
+    assert cache.get(exe_hash, "version --version") is None
+    cache.put(exe_hash, "version --version", "Python 3.8.10")
+    cache.save()
+
+    # a restart reads the results of the previous run
+    cache = DetectionCache(str(cache_path))
+    assert cache.get(exe_hash, "version --version") == "Python 3.8.10"
+    assert cache.get(exe_hash, "version -V") is None
+    assert json.loads(cache_path.read_text())["entries"].keys() == {f"version --version:{exe_hash}"}
+
+
+def test_corrupt_detection_cache(tmp_path: Path) -> None:
+    cache_path = tmp_path / "detection_cache.json"
+    cache_path.write_text("{")
+    assert DetectionCache(str(cache_path)).get("0" * 64, "version --version") is None
@@ -0,0 +1,67 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+import datetime
+import json
+from pathlib import Path
+from typing import Dict, List, Optional
+
+from gprofiler.downsample import downsample_directory
+from gprofiler.utils.collapsed_format import parse_one_collapsed
+
+
+def write_session_file(
+    directory: Path, end_time: str, stacks: Dict[str, int], app_metadata: List[Optional[Dict]], cpu_avg: float
+) -> None:
+    header = {
+        "schema_version": 1,
+        "application_metadata": app_metadata,
+        "application_metadata_enabled": True,
+        "metrics": {"cpu_avg": cpu_avg, "mem_avg": None},
+        "flamegraph_html": "<html></html>",
+    }
+    lines = ["# " + json.dumps(header)] + [f"{stack} {count}" for stack, count in stacks.items()]
+    (directory / f"profile_{end_time}.col").write_text("\n".join(lines) + "\n")
+
+
+def test_downsample_directory(tmp_path: Path) -> None:
+    input_dir = tmp_path / "input"
+    output_dir = tmp_path / "output"
+    input_dir.mkdir()
+    output_dir.mkdir()
+    write_session_file(input_dir, "2024-01-01T10:01:00", {"0;;python;main": 10}, [None], 10.0)
+    # the application metadata indices differ between the files
+    write_session_file(
+        input_dir, "2024-01-01T10:02:00", {"1;;java;main": 5, "0;;python;main": 1}, [None, {"version": "17"}], 30.0
+    )
+    # in the next window, which hasn't ended yet
+    write_session_file(input_dir, "2024-01-01T10:06:00", {"0;;python;main": 7}, [None], 10.0)
+
+    written, merged = downsample_directory(
+        str(input_dir), str(output_dir), 300, datetime.datetime.fromisoformat("2024-01-01T10:08:00")
+    )
+
+    assert written == [str(output_dir / "profile_2024-01-01T10:05:00.col")]
+    assert merged == [
+        str(input_dir / "profile_2024-01-01T10:01:00.col"),
+        str(input_dir / "profile_2024-01-01T10:02:00.col"),
+    ]
+    collapsed = Path(written[0]).read_text()
+    header = json.loads(collapsed.split("\n", 1)[0][1:])
+    assert header["application_metadata"] == [None, {"version": "17"}]
+    assert header["metrics"] == {"cpu_avg": 20.0, "mem_avg": None}
+    assert header["flamegraph_html"] is None
+    assert header["downsample"] == {"window": 300, "end_time": "2024-01-01T10:05:00", "windows": 2}
+    assert parse_one_collapsed(collapsed) == {"0;;python;main": 11, "1;;java;main": 5}
@@ -0,0 +1,84 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+import json
+from pathlib import Path
+
+import pytest
+from psutil import AccessDenied, NoSuchProcess
+
+from gprofiler.error_records import (
+    ACCESS_DENIED,
+    OTHER,
+    PROCESS_EXITED,
+    READ_FAULT,
+    VERSION_UNSUPPORTED,
+    ErrorRecord,
+    ErrorRecordsWriter,
+    classify_exception,
+    classify_message,
+)
+from gprofiler.exceptions import CalledProcessError
+
+
+@pytest.mark.parametrize(
+    "exception,expected",
+    [
+        pytest.param(NoSuchProcess(1234), PROCESS_EXITED, id="no-such-process"),
+        pytest.param(AccessDenied(1234), ACCESS_DENIED, id="access-denied"),
+        pytest.param(
+            CalledProcessError(1, ["py-spy"], "", "Error: Failed to copy PyCodeObject\n"), READ_FAULT, id="read-fault"
+        ),
+        pytest.param(
+            CalledProcessError(1, ["py-spy"], "", "Error: Permission denied (os error 13)\n"),
+            ACCESS_DENIED,
+            id="py-spy-permission",
+        ),
+        pytest.param(ValueError("Unsupported Python version 2.6"), VERSION_UNSUPPORTED, id="unsupported-version"),
+        pytest.param(ValueError("boom"), OTHER, id="other"),
+    ],
+)
+def test_classify_exception(exception: BaseException, expected: str) -> None:
+    assert classify_exception(exception) == expected
+
+
+def test_classify_message() -> None:
+    assert classify_message("profiling this JVM is not supported") == VERSION_UNSUPPORTED
+    assert classify_message("process exited before py-spy started") == PROCESS_EXITED
+
+
+def test_error_records_writer(tmp_path: Path) -> None:
+    path = tmp_path / "errors.jsonl"
+    writer = ErrorRecordsWriter(str(path))
+    writer.write(ErrorRecord("python", 1234, "python", READ_FAULT, "CalledProcessError", "x" * 5000))
+    writer.write(ErrorRecord("java", 5678, "java", VERSION_UNSUPPORTED, None, "profiling this JVM is not supported"))
+
+    records = [json.loads(line) for line in path.read_text().splitlines()]
+    assert [(record["profiler"], record["pid"], record["kind"]) for record in records] == [
+        ("python", 1234, READ_FAULT),
+        ("java", 5678, VERSION_UNSUPPORTED),
+    ]
+    assert records[1]["exception"] is None
+    assert len(records[0]["message"]) == 1000
+    # like the output files
+    assert path.stat().st_mode & 0o777 == 0o600
+
+
+def test_error_records_world_readable(tmp_path: Path) -> None:
+    path = tmp_path / "errors.jsonl"
+    ErrorRecordsWriter(str(path), world_readable=True).write(
+        ErrorRecord("python", 1234, "python", READ_FAULT, None, "failed")
+    )
+    assert path.stat().st_mode & 0o777 == 0o644
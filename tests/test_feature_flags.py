@@ -0,0 +1,71 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+import json
+import os
+from pathlib import Path
+
+import pytest
+from psutil import Process
+
+from gprofiler.feature_flags import (
+    APPLICATION_IDENTIFIERS,
+    APPLICATION_METADATA,
+    NATIVE_UNWINDING,
+    FeatureFlags,
+    FeatureFlagsError,
+    parse_kill_switches,
+)
+
+
+def test_kill_switches_per_process(tmp_path: Path) -> None:
+    path = tmp_path / "feature_flags.json"
+    path.write_text(json.dumps({"kill_switches": [{"feature": APPLICATION_METADATA, "process": "*python*"}]}))
+    flags = FeatureFlags()
+    flags.set_file(str(path))
+
+    assert flags.is_disabled(APPLICATION_METADATA, Process())
+    assert not flags.is_disabled(APPLICATION_IDENTIFIERS, Process())
+    assert not flags.is_disabled_for_any(NATIVE_UNWINDING, [Process()])
+
+    # the file is re-read when it changes
+    path.write_text(json.dumps({"kill_switches": [{"feature": NATIVE_UNWINDING, "process": "no-such-process"}]}))
+    os.utime(path, (0, 0))
+    flags.reload_file()
+    assert not flags.is_disabled(APPLICATION_METADATA, Process())
+    assert not flags.is_disabled_for_any(NATIVE_UNWINDING, [Process()])
+
+
+def test_control_kill_switches() -> None:
+    flags = FeatureFlags()
+    flags.set_control_switches({"kill_switches": [{"feature": NATIVE_UNWINDING}]})
+    assert flags.is_disabled_for_any(NATIVE_UNWINDING, [])
+
+    # invalid kill switches from the server are ignored, keeping the previous ones
+    flags.set_control_switches({"kill_switches": [{"feature": "no-such-feature"}]})
+    assert flags.is_disabled_for_any(NATIVE_UNWINDING, [])
+
+
+@pytest.mark.parametrize(
+    "config",
+    [
+        pytest.param([], id="not-an-object"),
+        pytest.param({"kill_switches": [{"feature": "no-such-feature"}]}, id="unknown-feature"),
+        pytest.param({"kill_switches": [{"feature": NATIVE_UNWINDING, "process": 1}]}, id="invalid-process"),
+    ],
+)
+def test_invalid_kill_switches(config: object) -> None:
+    with pytest.raises(FeatureFlagsError):
+        parse_kill_switches(config)
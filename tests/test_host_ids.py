@@ -0,0 +1,28 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+import uuid
+
+from gprofiler.metadata.system_metadata import machine_app_specific_id
+
+
+def test_machine_app_specific_id() -> None:
+    machine_id = "b08dfa6083e7567a1921a715000001fb"
+    host_id = machine_app_specific_id(machine_id)
+    # stable, a v4 UUID, and not the machine-id itself
+    assert host_id == machine_app_specific_id(machine_id)
+    assert uuid.UUID(host_id).version == 4
+    assert uuid.UUID(host_id).hex != machine_id
+    assert host_id != machine_app_specific_id("a08dfa6083e7567a1921a715000001fb")
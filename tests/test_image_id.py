@@ -0,0 +1,51 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+from threading import Event
+from typing import Iterator
+
+import pytest
+from psutil import AccessDenied, Process
+from pytest import MonkeyPatch
+
+from gprofiler.metadata import application_metadata
+from gprofiler.metadata.application_metadata import ApplicationMetadata
+
+
+@pytest.fixture
+def metadata(monkeypatch: MonkeyPatch) -> ApplicationMetadata:
+    monkeypatch.setattr(ApplicationMetadata, "_cache", {})
+    monkeypatch.setattr(ApplicationMetadata, "make_application_metadata", lambda self, process: {"exe": "python3"})
+    return ApplicationMetadata(Event())
+
+
+def test_replaced_program_metadata(monkeypatch: MonkeyPatch, metadata: ApplicationMetadata) -> None:
+    image_ids: Iterator[str] = iter(["a", "a", "b"])
+    monkeypatch.setattr(application_metadata, "process_image_id", lambda process: next(image_ids))
+    process = Process()
+
+    assert metadata.get_metadata(process) == {"exe": "python3"}
+    assert metadata.get_metadata(process) == {"exe": "python3"}
+    # the process exec()ed into another program - its metadata (thus the root frame of its stacks) is its own
+    assert metadata.get_metadata(process) == {"exe": "python3", "image_id": "b"}
+
+
+def test_exe_version_image_id_denied(monkeypatch: MonkeyPatch, metadata: ApplicationMetadata) -> None:
+    def denied(process: Process) -> str:
+        raise AccessDenied(process.pid)
+
+    monkeypatch.setattr(application_metadata, "process_image_id", denied)
+    monkeypatch.setattr(ApplicationMetadata, "get_exe_version", lambda self, process, version_arg, try_stderr: "1.0")
+    assert metadata.get_exe_version_cached(Process()) == "1.0"
@@ -0,0 +1,45 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+import logging
+
+import pytest
+
+from gprofiler.log import LogLevelFilter, parse_log_filter
+
+
+def make_record(name: str, level: int) -> logging.LogRecord:
+    return logging.LogRecord(name, level, __file__, 0, "message", None, None)
+
+
+def test_log_filter() -> None:
+    default_level, levels = parse_log_filter("warning, profilers.python=debug,gprofiler.client=info")
+    assert default_level == logging.WARNING
+    assert levels == {"gprofiler.profilers.python": logging.DEBUG, "gprofiler.client": logging.INFO}
+
+    log_filter = LogLevelFilter(default_level, levels)
+    assert log_filter.min_level == logging.DEBUG
+    # submodules get the level of their module
+    assert log_filter.filter(make_record("gprofiler.profilers.python", logging.DEBUG))
+    assert log_filter.filter(make_record("gprofiler.profilers.python.pyspy", logging.DEBUG))
+    assert not log_filter.filter(make_record("gprofiler.profilers.python_ebpf", logging.INFO))
+    assert not log_filter.filter(make_record("gprofiler.client", logging.DEBUG))
+    assert log_filter.filter(make_record("gprofiler", logging.WARNING))
+
+
+@pytest.mark.parametrize("spec", ["python=verbose", "=debug"])
+def test_invalid_log_filter(spec: str) -> None:
+    with pytest.raises(ValueError):
+        parse_log_filter(spec)
@@ -0,0 +1,51 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+import json
+from pathlib import Path
+
+import pytest
+
+from gprofiler.utils.manifest import ManifestError, find_session_manifest, make_manifest, verify_file
+
+
+def test_session_manifest(tmp_path: Path) -> None:
+    collapsed_path = tmp_path / "profile_2022-01-01T00-00-00.col"
+    collapsed_path.write_text("# {}\npython;a 1\n")
+    summary_path = tmp_path / "profile_2022-01-01T00-00-00.summary.json"
+    summary_path.write_text("{}")
+    manifest_path = tmp_path / "profile_2022-01-01T00-00-00.manifest.json"
+    manifest_path.write_text(json.dumps(make_manifest([str(collapsed_path), str(summary_path)])))
+    last_profile = tmp_path / "last_profile.col"
+    last_profile.symlink_to(collapsed_path.name)
+
+    assert find_session_manifest(str(last_profile)) == manifest_path
+    verify_file(manifest_path, str(last_profile))
+
+    # partially written
+    collapsed_path.write_text("# {}\npython;a")
+    with pytest.raises(ManifestError, match="partially written"):
+        verify_file(manifest_path, str(collapsed_path))
+
+    # corrupted
+    summary_path.write_text("[]")
+    with pytest.raises(ManifestError, match="corrupted"):
+        verify_file(manifest_path, str(summary_path))
+
+
+def test_no_session_manifest(tmp_path: Path) -> None:
+    collapsed_path = tmp_path / "external.col"
+    collapsed_path.write_text("# {}\npython;a 1\n")
+    assert find_session_manifest(str(collapsed_path)) is None
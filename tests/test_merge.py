@@ -18,16 +18,29 @@
 Tests for the logic from gprofiler/merge.py
 """
 
+import json
+from pathlib import Path
 from typing import Dict
 
 import pytest
 from granulate_utils.metadata import Metadata
 
-from gprofiler.gprofiler_types import ProcessToProfileData, ProcessToStackSampleCounters, ProfileData
-from gprofiler.merge import merge_profiles
+from gprofiler.gprofiler_types import (
+    ProcessToProfileData,
+    ProcessToStackSampleCounters,
+    ProfileData,
+    StackToSampleCount,
+)
+from gprofiler.merge import (
+    concatenate_from_external_file,
+    merge_profiles,
+    normalize_sample_weights,
+    scale_sample_counts,
+    seed_samples_random,
+)
 from gprofiler.metadata.enrichment import EnrichmentOptions
 from gprofiler.system_metrics import Metrics
-from gprofiler.utils.collapsed_format import parse_many_collapsed
+from gprofiler.utils.collapsed_format import insert_frame_after_comm, parse_many_collapsed
 
 
 def parse_profiles_text(profiles_text: str) -> ProcessToProfileData:
@@ -121,3 +134,39 @@ def test_merge_profiles_onto_errors(input_dict: Dict[str, str], expected: str) -
     header, outcome = header_outcome[0], header_outcome[1] if len(header_outcome) == 2 else ""
     assert header.startswith("#")
     assert expected == outcome
+
+
+def test_scale_sample_counts_seeded() -> None:
+    stacks = StackToSampleCount({f"python;frame_{i}": i for i in range(1, 100)})
+
+    seed_samples_random(1234)
+    first = scale_sample_counts(stacks, 0.37)
+    seed_samples_random(1234)
+    second = scale_sample_counts(stacks, 0.37)
+
+    assert first == second
+
+
+def test_normalize_sample_weights() -> None:
+    # collected at 50Hz, the output is at 100Hz: each sample represents 2 intervals.
+    process_profiles = parse_profiles_text("python-1;a;b 10\npython-1;a;c 5\n")
+    normalize_sample_weights(process_profiles, 50, 100)
+    assert dict(process_profiles[1].stacks) == {"python;a;b": 20, "python;a;c": 10}
+
+
+def test_insert_frame_after_comm() -> None:
+    stacks = StackToSampleCount({"python;a;b": 3, "python": 1})
+    assert insert_frame_after_comm(stacks, "[warmup]") == {"python;[warmup];a;b": 3, "python;[warmup]": 1}
+
+
+def test_concatenate_from_external_file_schema_version(tmp_path: Path) -> None:
+    # files written before schema versions were introduced are read as the first version
+    legacy_path = tmp_path / "legacy.col"
+    legacy_path.write_text(f"# {json.dumps({'profiling_mode': 'cpu'})}\npython;a 1\n")
+    _, _, profile = concatenate_from_external_file(str(legacy_path), {})
+    assert profile.splitlines()[1] == "python;a 1"
+
+    future_path = tmp_path / "future.col"
+    future_path.write_text(f"# {json.dumps({'schema_version': 1000})}\npython;a 1\n")
+    with pytest.raises(ValueError, match="Unsupported schema version 1000"):
+        concatenate_from_external_file(str(future_path), {})
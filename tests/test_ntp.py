@@ -0,0 +1,53 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+
+import socket
+import struct
+import time
+from threading import Thread
+
+import pytest
+
+from gprofiler.utils.ntp import NTP_EPOCH_OFFSET, NTP_PORT, parse_ntp_server, query_clock_offset
+
+
+def _to_ntp_timestamp(timestamp: float) -> bytes:
+    timestamp += NTP_EPOCH_OFFSET
+    return struct.pack("!II", int(timestamp), int((timestamp % 1) * 2**32))
+
+
+def _serve_one_request(s: socket.socket, clock_offset: float) -> None:
+    _, address = s.recvfrom(48)
+    now = _to_ntp_timestamp(time.time() + clock_offset)
+    # header (LI/VN/Mode = server, stratum, ...) up to the receive & transmit timestamps.
+    s.sendto(struct.pack("!B", 0x1C) + b"\0" * 31 + now + now, address)
+
+
+@pytest.mark.parametrize("clock_offset", [-30.0, 0.0, 120.0])
+def test_query_clock_offset(clock_offset: float) -> None:
+    with socket.socket(socket.AF_INET, socket.SOCK_DGRAM) as s:
+        s.bind(("127.0.0.1", 0))
+        server = Thread(target=_serve_one_request, args=(s, clock_offset))
+        server.start()
+        offset = query_clock_offset(f"127.0.0.1:{s.getsockname()[1]}")
+        server.join()
+
+    assert offset == pytest.approx(clock_offset, abs=0.5)
+
+
+def test_parse_ntp_server() -> None:
+    assert parse_ntp_server("pool.ntp.org") == ("pool.ntp.org", NTP_PORT)
+    assert parse_ntp_server("10.0.0.1:1123") == ("10.0.0.1", 1123)
@@ -0,0 +1,126 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+
+"""
+Tests for the output formats from gprofiler/output_formats/
+"""
+
+import datetime
+import json
+from collections import Counter
+
+import pytest
+
+from gprofiler.output_formats.cpuprofile import CpuProfileOutputFormat
+from gprofiler.output_formats.csv_functions import CsvFunctionsByProcessOutputFormat, CsvFunctionsOutputFormat
+from gprofiler.output_formats.output_format import OutputContext, OutputFormatOptionError
+from gprofiler.output_formats.registry import parse_format_options
+from gprofiler.output_formats.speedscope import SpeedscopeOutputFormat
+
+START_TIME = datetime.datetime(2022, 1, 1, tzinfo=datetime.timezone.utc)
+CONTEXT = OutputContext(START_TIME, START_TIME + datetime.timedelta(seconds=60), sampling_frequency=10)
+
+
+def test_cpuprofile() -> None:
+    stacks = Counter(
+        {
+            "python;<module> (/app/main.py:10)_[p];compute (/app/main.py:42)_[p]": 3,
+            "python;<module> (/app/main.py:10)_[p];PyEval_EvalFrame_[pn]": 1,
+        }
+    )
+
+    cpuprofile = json.loads(CpuProfileOutputFormat().write(stacks, CONTEXT))
+
+    nodes = {node["id"]: node for node in cpuprofile["nodes"]}
+    assert nodes[1]["callFrame"]["functionName"] == "(root)"
+    (python_id,) = nodes[1]["children"]
+    assert nodes[python_id]["callFrame"]["functionName"] == "python"
+    (module_id,) = nodes[python_id]["children"]
+    assert nodes[module_id]["callFrame"]["url"] == "/app/main.py"
+    assert nodes[module_id]["callFrame"]["lineNumber"] == 9  # 0-based
+    compute_id, native_id = nodes[module_id]["children"]
+    assert nodes[compute_id]["callFrame"]["functionName"] == "compute"
+    assert nodes[native_id]["callFrame"]["functionName"] == "PyEval_EvalFrame"
+
+    assert cpuprofile["samples"] == [compute_id] * 3 + [native_id]
+    assert nodes[compute_id]["hitCount"] == 3
+    # 10 hertz - 100ms between samples
+    assert cpuprofile["timeDeltas"] == [100_000] * 4
+    assert cpuprofile["endTime"] - cpuprofile["startTime"] == 400_000
+
+
+def test_csv_functions() -> None:
+    stacks = Counter(
+        {
+            "python;main (/app/main.py:1)_[p];fib (/app/main.py:5)_[p];fib (/app/main.py:5)_[p]": 6,
+            "python;main (/app/main.py:1)_[p]": 2,
+            "java;java/lang/Thread.run_[j]": 2,
+        }
+    )
+
+    rows = CsvFunctionsOutputFormat().write(stacks, CONTEXT).decode().splitlines()
+    assert rows == [
+        "function,module,self_samples,total_samples,self_percent,total_percent",
+        "main,/app/main.py,2,8,20.00,80.00",
+        # recursion is counted once
+        "fib,/app/main.py,6,6,60.00,60.00",
+        "java/lang/Thread.run,,2,2,20.00,20.00",
+    ]
+
+    rows = CsvFunctionsByProcessOutputFormat().write(stacks, CONTEXT).decode().splitlines()
+    assert rows[0] == "process,function,module,self_samples,total_samples,self_percent,total_percent"
+    assert rows[-1] == "java,java/lang/Thread.run,,2,2,20.00,20.00"
+
+
+def test_speedscope() -> None:
+    stacks = Counter(
+        {
+            "python;<module> (/app/main.py:10)_[p];compute (/app/main.py:42)_[p]": 3,
+            "java;main_[j]": 5,
+        }
+    )
+
+    speedscope = json.loads(SpeedscopeOutputFormat().write(stacks, CONTEXT))
+
+    assert speedscope["shared"]["frames"] == [
+        {"name": "<module>", "file": "/app/main.py", "line": 10},
+        {"name": "compute", "file": "/app/main.py", "line": 42},
+        {"name": "main"},
+    ]
+    # a profile per process name, the heaviest first
+    java, python = speedscope["profiles"]
+    assert (java["name"], java["samples"], java["weights"], java["endValue"]) == ("java", [[2]], [0.5], 0.5)
+    assert (python["name"], python["samples"], python["weights"]) == ("python", [[0, 1]], [0.3])
+    assert python["unit"] == "seconds"
+
+
+def test_format_options() -> None:
+    formats = ["csv-functions", "speedscope"]
+    assert parse_format_options(["sort=self", "speedscope.name=checkout"], formats) == {
+        "csv-functions": {"sort": "self"},
+        "speedscope": {"name": "checkout"},
+    }
+    for format_opts in (["sort=size"], ["title=x"], ["cpuprofile.name=x"], ["speedscope.title=x"], ["name"]):
+        with pytest.raises(OutputFormatOptionError):
+            parse_format_options(format_opts, formats)
+
+    stacks = Counter(
+        {"python;main (/app/main.py:1)_[p];fib (/app/main.py:5)_[p]": 6, "python;main (/app/main.py:1)_[p]": 1}
+    )
+    rows = CsvFunctionsOutputFormat({"sort": "self"}).write(stacks, CONTEXT).decode().splitlines()
+    assert rows[1].startswith("fib,")
+    speedscope = json.loads(SpeedscopeOutputFormat({"name": "checkout"}).write(stacks, CONTEXT))
+    assert speedscope["name"] == "checkout"
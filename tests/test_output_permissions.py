@@ -0,0 +1,57 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+from pathlib import Path
+
+import pytest
+
+from gprofiler.main import GProfiler
+from gprofiler.utils.fs import restrict_to_owner
+
+
+def make_gprofiler(output_dir: Path, world_readable: bool) -> GProfiler:
+    # just what writing the output files needs
+    gprofiler = GProfiler.__new__(GProfiler)
+    gprofiler._output_dir = str(output_dir)
+    gprofiler._world_readable = world_readable
+    gprofiler._encryption_recipients = None
+    gprofiler._rotating_output = False
+    return gprofiler
+
+
+@pytest.mark.parametrize("world_readable,expected_mode", [(False, 0o600), (True, 0o644)])
+def test_session_file_mode(tmp_path: Path, world_readable: bool, expected_mode: int) -> None:
+    gprofiler = make_gprofiler(tmp_path, world_readable)
+    path = gprofiler._write_output_file(str(tmp_path / "profile_1.col"), "last_profile.col", b"python;main 1\n")
+    assert Path(path).read_bytes() == b"python;main 1\n"
+    assert Path(path).stat().st_mode & 0o777 == expected_mode
+
+    # an existing file is restricted (or not) as well
+    path = gprofiler._write_output_file(str(tmp_path / "profile_1.col"), "last_profile.col", b"python;main 2\n")
+    assert Path(path).read_bytes() == b"python;main 2\n"
+    assert Path(path).stat().st_mode & 0o777 == expected_mode
+
+
+def test_restrict_to_owner(tmp_path: Path) -> None:
+    directory = tmp_path / "output"
+    directory.mkdir(mode=0o755)
+    file = directory / "profile.col"
+    file.write_bytes(b"")
+    file.chmod(0o644)
+
+    restrict_to_owner(directory)
+    restrict_to_owner(file)
+    assert directory.stat().st_mode & 0o777 == 0o700
+    assert file.stat().st_mode & 0o777 == 0o600
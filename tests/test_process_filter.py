@@ -0,0 +1,61 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+import os
+import re
+import sys
+
+import pytest
+from psutil import Process
+
+from gprofiler.utils.process_filter import ProcessFilter
+
+PYTHON_DIR = os.path.dirname(os.path.realpath(sys.executable))
+
+
+@pytest.mark.parametrize(
+    "process_filter, expected",
+    [
+        pytest.param(ProcessFilter(include_users=[Process().username()]), True, id="include-user"),
+        pytest.param(ProcessFilter(exclude_users=[Process().username()]), False, id="exclude-user"),
+        pytest.param(ProcessFilter(include_users=["no-such-user"]), False, id="include-other-user"),
+        pytest.param(ProcessFilter(include_paths=[f"{PYTHON_DIR}/**"]), True, id="include-path"),
+        pytest.param(ProcessFilter(exclude_paths=["*python*"]), False, id="exclude-path"),
+        pytest.param(ProcessFilter(include_sessions=[os.getsid(0)]), True, id="include-session"),
+        pytest.param(ProcessFilter(exclude_sessions=[os.getsid(0)]), False, id="exclude-session"),
+        pytest.param(ProcessFilter(include_uids=[str(os.geteuid())]), True, id="include-uid"),
+        pytest.param(ProcessFilter(exclude_uids=[str(os.geteuid())]), False, id="exclude-uid"),
+        pytest.param(ProcessFilter(include_cmdlines=[re.compile(r"pytest")]), True, id="include-cmdline"),
+        pytest.param(ProcessFilter(exclude_cmdlines=[re.compile(r"\bpytest\b")]), False, id="exclude-cmdline"),
+        pytest.param(ProcessFilter(include_cmdlines=[re.compile(r"^bash ")]), False, id="include-other-cmdline"),
+        pytest.param(ProcessFilter(current_user_only=True), True, id="current-user-only"),
+    ],
+)
+def test_process_filter(process_filter: ProcessFilter, expected: bool) -> None:
+    assert process_filter.matches(Process()) == expected
+
+
+def test_process_filter_from_args() -> None:
+    assert ProcessFilter.from_args({}) is None
+    process_filter = ProcessFilter.from_args({"exclude_users": ["SYSTEM"]})
+    assert process_filter is not None
+    assert process_filter.exclude_users == ["SYSTEM"]
+    # processes whose details can't be read are profiled unless an include filter is given
+    assert process_filter.matches_unknown()
+    assert not ProcessFilter(include_paths=["/opt/**"]).matches_unknown()
+    # rootless mode can't attach to processes of other users
+    process_filter = ProcessFilter.from_args({"rootless": True})
+    assert process_filter is not None and process_filter.current_user_only
+    assert not process_filter.matches_unknown()
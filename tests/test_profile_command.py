@@ -0,0 +1,56 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+import datetime
+import json
+import logging
+import os
+import signal
+import stat
+import subprocess
+from pathlib import Path
+
+from pytest import MonkeyPatch
+
+import gprofiler.main
+from gprofiler.main import command_exit_code, write_command_result
+from gprofiler.utils.manifest import verify_file
+
+
+def test_command_exit_code() -> None:
+    assert command_exit_code(0) == 0
+    assert command_exit_code(3) == 3
+
+    # killed by a signal, like the shells report it
+    command = subprocess.Popen(["sleep", "60"])
+    command.kill()
+    assert command.wait() == -signal.SIGKILL
+    assert command_exit_code(command.returncode) == 128 + signal.SIGKILL
+
+
+def test_write_command_result(tmp_path: Path, monkeypatch: MonkeyPatch) -> None:
+    # the module logger is only set up by main()
+    monkeypatch.setattr(gprofiler.main, "logger", logging.getLogger("gprofiler"), raising=False)
+    now = datetime.datetime.utcnow()
+    write_command_result(str(tmp_path), ["true"], 0, now, now)
+
+    result_path = tmp_path / "command.json"
+    assert json.loads(result_path.read_text())["exit_code"] == 0
+    manifest_path = tmp_path / "command.manifest.json"
+    for path in (result_path, manifest_path):
+        assert stat.S_IMODE(os.stat(path).st_mode) == 0o600
+
+    assert [entry["name"] for entry in json.loads(manifest_path.read_text())["files"]] == ["command.json"]
+    verify_file(manifest_path, str(result_path))
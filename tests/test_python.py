@@ -14,15 +14,20 @@
 # limitations under the License.
 #
 import os
+from typing import Any
 
 import psutil
 import pytest
 from granulate_utils.linux.process import is_musl
 from granulate_utils.type_utils import assert_cast
+from pytest import MonkeyPatch
 
+from gprofiler.exceptions import CalledProcessError
 from gprofiler.profiler_state import ProfilerState
-from gprofiler.profilers.python import PythonProfiler
+from gprofiler.profilers import python
+from gprofiler.profilers.python import PySpyProfiler, PythonProfiler
 from gprofiler.profilers.python_ebpf import PythonEbpfProfiler
+from gprofiler.utils.process import process_comm
 from tests.conftest import AssertInCollapsed
 from tests.utils import (
     assert_function_in_collapsed,
@@ -206,3 +211,27 @@ def test_dso_name_in_pyperf_profile(
     assert insert_dso_name == is_pattern_in_collapsed(
         rf"{interpreter_frame} \(.+?/libpython{python_version}.*?\.so.*?\)_\[pn\]", collapsed
     )
+
+
+def test_pyspy_process_exited_marker(monkeypatch: MonkeyPatch, profiler_state: ProfilerState) -> None:
+    """
+    The marker stack of a process that exited before py-spy started - the comm is its first frame, as in all the
+    error marker stacks.
+    """
+
+    def exited(*args: Any, **kwargs: Any) -> None:
+        raise CalledProcessError(
+            1, ["py-spy"], "", "Error: Failed to get process executable name. Check that the process is running.\n"
+        )
+
+    monkeypatch.setattr(python, "run_process", exited)
+    monkeypatch.setattr(python, "is_process_running", lambda process: False)
+    monkeypatch.setattr(python, "resource_path", lambda path: path)
+    monkeypatch.setattr(python.application_identifiers, "get_python_app_id", lambda process: None)
+    monkeypatch.setattr(profiler_state, "get_container_name", lambda pid: "")
+    profiler = PySpyProfiler(11, 1, profiler_state, add_versions=False, python_pyspy_process=[])
+    monkeypatch.setattr(profiler._metadata, "get_metadata", lambda process: None)
+
+    process = psutil.Process()
+    profile = profiler._profile_process(process, 1, False)
+    assert profile.stacks == {f"{process_comm(process)};[Profiling error: process exited before py-spy started]": 1}
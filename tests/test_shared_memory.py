@@ -0,0 +1,72 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+import os
+import struct
+from pathlib import Path
+from typing import Tuple
+
+from gprofiler.utils.shared_memory import HEADER, SHARED_MEMORY_MAGIC, SharedMemoryPublisher
+
+
+def read_shared_memory(path: Path) -> Tuple[int, int, bytes]:
+    """
+    Reads the section like a sidecar reader would, following the seqlock protocol.
+    """
+    while True:
+        section = path.read_bytes()
+        magic, _, sequence, length, end_time_ms = HEADER.unpack_from(section)
+        assert magic == SHARED_MEMORY_MAGIC
+        data = section[HEADER.size : HEADER.size + length]
+        if sequence % 2 == 0 and struct.unpack_from("<Q", path.read_bytes(), 8)[0] == sequence:
+            return sequence, end_time_ms, data
+
+
+def test_shared_memory_publisher() -> None:
+    name = f"gprofiler-test-{os.getpid()}"
+    publisher = SharedMemoryPublisher(name, 4096)
+    path = Path("/dev/shm") / name
+    try:
+        assert read_shared_memory(path) == (0, 0, b"")
+
+        assert publisher.publish(b"python;main;work 10\n", 1000)
+        assert read_shared_memory(path) == (2, 1000, b"python;main;work 10\n")
+
+        # a smaller profile replaces the previous one entirely
+        assert publisher.publish(b"python;main 1\n", 2000)
+        assert read_shared_memory(path) == (4, 2000, b"python;main 1\n")
+
+        # profiles which don't fit aren't published
+        assert not publisher.publish(b"x" * 4096, 3000)
+        assert read_shared_memory(path) == (4, 2000, b"python;main 1\n")
+    finally:
+        publisher.close()
+    assert not path.exists()
+
+
+def test_shared_memory_replaces_existing(tmp_path: Path) -> None:
+    name = f"gprofiler-test-{os.getpid()}"
+    path = Path("/dev/shm") / name
+    target = tmp_path / "target"
+    target.write_bytes(b"not ours")
+    # e.g planted by another user
+    path.symlink_to(target)
+    publisher = SharedMemoryPublisher(name, 4096)
+    try:
+        assert not path.is_symlink()
+        assert target.read_bytes() == b"not ours"
+        assert path.stat().st_mode & 0o777 == 0o600
+    finally:
+        publisher.close()
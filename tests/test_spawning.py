@@ -0,0 +1,127 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+import re
+import subprocess
+import time
+from collections import Counter
+from typing import Iterator, List
+
+import pytest
+from psutil import Process
+from pytest import MonkeyPatch
+
+from gprofiler.gprofiler_types import ProfileData
+from gprofiler.profiler_state import ProfilerState
+from gprofiler.profilers.profiler_base import SpawningProcessProfilerBase
+from gprofiler.utils.process_filter import ProcessFilter
+
+
+class FakeSpawningProfiler(SpawningProcessProfilerBase):
+    name = "fake"
+
+    def _select_processes_to_profile(self) -> List[Process]:
+        return []
+
+    def _should_profile_process(self, process: Process) -> bool:
+        return True
+
+    def _profile_process(self, process: Process, duration: int, spawned: bool) -> ProfileData:
+        return ProfileData(Counter({"fake;main": 1}), None, None, None)
+
+
+@pytest.fixture
+def spawned_process() -> Iterator[Process]:
+    # a grandchild - gProfiler never profiles its own children.
+    shell = subprocess.Popen(["sh", "-c", "sleep 60 & wait"])
+    try:
+        for _ in range(100):
+            children = Process(shell.pid).children()
+            if children:
+                break
+            time.sleep(0.05)
+        assert children, "sleep wasn't spawned"
+        yield children[0]
+    finally:
+        for child in Process(shell.pid).children():
+            child.kill()
+        shell.kill()
+        shell.wait()
+
+
+def check_spawned(profiler_state: ProfilerState, process: Process) -> bool:
+    """
+    Whether the profiler starts profiling the newly spawned process.
+    """
+    profiler = FakeSpawningProfiler(11, 5, profiler_state)
+    profiler._start_profiling_spawning([])
+    try:
+        profiler._check_process(process, profiler._BACKOFF_INIT)
+        return len(profiler._futures) == 1
+    finally:
+        profiler._stop_profiling_spawning()
+
+
+def test_spawned_process_filter(profiler_state: ProfilerState, spawned_process: Process) -> None:
+    assert check_spawned(profiler_state, spawned_process)
+    profiler_state.process_filter = ProcessFilter(exclude_cmdlines=[re.compile(r"^sleep 60$")])
+    assert not check_spawned(profiler_state, spawned_process)
+
+
+class OtherUserProcess(Process):
+    def username(self) -> str:
+        return "gprofiler-test-other-user"
+
+
+def test_spawned_process_rootless(profiler_state: ProfilerState, spawned_process: Process) -> None:
+    profiler_state.process_filter = ProcessFilter.from_args({"rootless": True})
+    assert check_spawned(profiler_state, spawned_process)
+    # rootless mode can't attach to the processes of other users
+    assert not check_spawned(profiler_state, OtherUserProcess(spawned_process.pid))
+
+
+def test_spawned_processes_count_against_new_processes_limit(
+    profiler_state: ProfilerState, spawned_process: Process
+) -> None:
+    profiler_state.max_new_processes_per_session = 1
+    profiler = FakeSpawningProfiler(11, 5, profiler_state)
+    # the session started one new process already
+    profiler._limit_new_processes([Process()], 1)
+    profiler._start_profiling_spawning([Process()])
+    try:
+        profiler._check_process(spawned_process, profiler._BACKOFF_INIT)
+        assert profiler._futures == {}
+    finally:
+        profiler._stop_profiling_spawning()
+
+
+def test_spawned_process_paused_for_debugger(
+    profiler_state: ProfilerState, spawned_process: Process, monkeypatch: MonkeyPatch
+) -> None:
+    profiler = FakeSpawningProfiler(11, 5, profiler_state)
+    monkeypatch.setattr(profiler, "_pause_for_debugger", lambda process, comm: True)
+    profiler._start_profiling_spawning([])
+    try:
+        profiler._check_process(spawned_process, profiler._BACKOFF_INIT)
+        assert profiler._futures == {}
+        # checked again later, to resume once the debugger detaches
+        assert [event.argument[0] for event in profiler._sched.queue] == [spawned_process]
+
+        monkeypatch.setattr(profiler, "_pause_for_debugger", lambda process, comm: False)
+        profiler._check_process(spawned_process, profiler._BACKOFF_INIT)
+        assert len(profiler._futures) == 1
+    finally:
+        profiler._clear_sched()
+        profiler._stop_profiling_spawning()
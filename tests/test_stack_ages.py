@@ -0,0 +1,39 @@
+#
+# Copyright (C) 2022 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#    http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+import os
+from collections import Counter
+from pathlib import Path
+
+from gprofiler.analysis.stack_ages import StackAges, read_marker_time
+
+
+def test_stacks_since_marker(tmp_path: Path) -> None:
+    marker = tmp_path / "deployed"
+    assert read_marker_time(str(marker)) is None
+
+    stack_ages = StackAges()
+    stack_ages.update(Counter({"python;main;old": 10}), 1000.0)
+    marker.touch()
+    os.utime(marker, (1030.0, 1030.0))
+    # the session during which the marker was set
+    stack_ages.update(Counter({"python;main;old": 5, "python;main;during": 1}), 1060.0)
+    stacks = Counter({"python;main;old": 3, "python;main;during": 2, "python;main;new": 7})
+    stack_ages.update(stacks, 1120.0)
+
+    assert stack_ages.first_seen("python;main;old") == 1000.0
+    marker_time = read_marker_time(str(marker))
+    assert marker_time == 1030.0
+    assert stack_ages.stacks_since(stacks, marker_time) == {"python;main;during": 2, "python;main;new": 7}